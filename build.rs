@@ -1,15 +1,70 @@
+use std::path::PathBuf;
+
+/// The library that will be linked.
+struct Library {
+    // only needed to find `mapper.h`
+    #[cfg_attr(not(feature = "bindgen"), allow(dead_code))]
+    include_paths: Vec<PathBuf>
+}
+
 fn main() {
+    println!("cargo:rustc-check-cfg=cfg(generated_bindings)");
+
     if std::env::var("DOCS_RS").is_ok() {
         return; // don't try to find libmapper on docs.rs
     }
+    if std::env::var("CARGO_FEATURE_FAKE_BACKEND").is_ok() {
+        return; // the fake backend doesn't link against libmapper
+    }
+
+    let library = link_system();
+    #[cfg(feature = "bindgen")]
+    generate_bindings(&library);
+}
 
+/// Link against an installed libmapper.
+///
+/// On Windows there is no pkg-config, so the version can't be checked
+/// and headers are looked up in `LIBMAPPER_INCLUDE`.
+fn link_system() -> Library {
     if cfg!(windows) {
         println!("cargo:rustc-link-lib=libmapper");
         println!("cargo:rustc-link-lib=liblo");
+        println!("cargo:rerun-if-env-changed=LIBMAPPER_INCLUDE");
+        Library {
+            include_paths: std::env::var_os("LIBMAPPER_INCLUDE").map(PathBuf::from).into_iter().collect()
+        }
     } else {
-        pkg_config::Config::new()
+        let library = pkg_config::Config::new()
             .atleast_version("2.4.9")
             .probe("libmapper")
             .unwrap();
+        Library {
+            include_paths: library.include_paths
+        }
     }
-}
\ No newline at end of file
+}
+
+/// Generate bindings from the linked library's `mapper.h`, replacing the checked-in `src/bindings.rs`.
+#[cfg(feature = "bindgen")]
+fn generate_bindings(library: &Library) {
+    let header = library.include_paths.iter()
+        .map(|p| p.join("mapper").join("mapper.h"))
+        .find(|p| p.exists())
+        .unwrap_or_else(|| PathBuf::from("mapper/mapper.h"));
+    let bindings = bindgen::Builder::default()
+        .header(header.to_string_lossy())
+        .clang_args(library.include_paths.iter().map(|p| format!("-I{}", p.display())))
+        .allowlist_function("mpr_.*")
+        .allowlist_type("mpr_.*")
+        .default_enum_style(bindgen::EnumVariation::Rust { non_exhaustive: false })
+        // timetags are passed around as a single 64 bit value, see `time.rs`
+        .blocklist_type("mpr_time")
+        .raw_line("pub type mpr_time = ::std::os::raw::c_ulonglong;")
+        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
+        .generate()
+        .expect("failed to generate libmapper bindings");
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    bindings.write_to_file(out_dir.join("bindings.rs")).unwrap();
+    println!("cargo:rustc-cfg=generated_bindings");
+}
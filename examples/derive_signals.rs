@@ -0,0 +1,45 @@
+//! # Example: Derive
+//! Declares a device's signals as a struct, and moves values between the struct and the signals every frame.
+//! Run with `cargo run --example derive_signals --features derive`.
+
+use std::time::Duration;
+
+use libmapper_rs::{device::DeviceBuilder, signal::MapperSignals};
+
+#[derive(MapperSignals, Default, Debug)]
+struct Synth {
+    #[signal(dir = "in", unit = "Hz", min = 20.0, max = 20000.0)]
+    freq: f32,
+    #[signal(dir = "in", min = 0.0, max = 1.0)]
+    gain: f64,
+    #[signal(dir = "out", name = "env/position", min = [-1.0, -1.0, 0.0], max = [1.0, 1.0, 10.0])]
+    position: [f32; 3],
+    #[signal(dir = "out")]
+    voices: i32,
+    // fields without a #[signal] attribute are not mapped
+    frame: u64,
+}
+
+fn main() {
+    let dev = DeviceBuilder::new("synth")
+        .build_and_wait(Duration::from_secs(10))
+        .expect("Device did not become ready");
+
+    let mut signals = Synth::create_signals(&dev).expect("Failed to create signals");
+    let mut synth = Synth::default();
+
+    loop {
+        dev.poll_and_block(Duration::from_millis(10));
+        synth.read_all(&signals);
+
+        synth.frame += 1;
+        let t = synth.frame as f32 / 100.0;
+        synth.position = [t.sin(), t.cos(), synth.gain as f32];
+        synth.voices = (synth.freq / 1000.0) as i32;
+
+        synth.write_all(&mut signals).unwrap();
+        if synth.frame % 100 == 0 {
+            println!("{:?}", synth);
+        }
+    }
+}
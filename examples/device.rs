@@ -1,16 +1,12 @@
 use std::time::Duration;
 
-use libmapper_rs::device::Device;
+use libmapper_rs::device::DeviceBuilder;
 
 fn main() {
     println!("Using libmapper version {} ", libmapper_rs::get_mapper_version());
-    let dev = Device::create("rustmapper");
-    loop {
-        dev.poll_and_block(Duration::from_millis(10));
-        if dev.is_ready() {
-            break;
-        }
-    }
+    let _dev = DeviceBuilder::new("rustmapper")
+        .build_and_wait(Duration::from_secs(10))
+        .expect("Device did not become ready");
 
     println!("Device became ready!");
-}
\ No newline at end of file
+}
@@ -14,4 +14,5 @@ fn main() {
   }
   let p = dev.get_property::<i64>(mpr_prop::MPR_PROP_ID).unwrap();
   println!("Device ID: {}", p);
+  println!("Device ID (hex): {}", dev.id());
 }
\ No newline at end of file
@@ -0,0 +1,39 @@
+//! # Example: Session
+//! Saves every map between the given devices to a session file, or restores a previously saved session.
+//! Run with `cargo run --example session --features session -- save patch.json synth controller`
+//! or `cargo run --example session --features session -- load patch.json`.
+
+use std::time::Duration;
+
+use libmapper_rs::{constants::mpr_type, graph::Graph, session::Session};
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (command, path) = match (args.first(), args.get(1)) {
+        (Some(command), Some(path)) => (command.as_str(), path),
+        _ => {
+            eprintln!("usage: session <save|load> <file> [devices...]");
+            return;
+        }
+    };
+
+    let graph = Graph::create();
+    match command {
+        "save" => {
+            graph.subscribe(None, &[mpr_type::MPR_DEV, mpr_type::MPR_SIG, mpr_type::MPR_MAP]);
+            graph.poll_and_block(Duration::from_secs(2));
+            let devices: Vec<&str> = args[2..].iter().map(String::as_str).collect();
+            let session = Session::capture(&graph, &devices);
+            session.save(path).expect("Failed to save session");
+            println!("Saved {} maps to {}", session.maps.len(), path);
+        }
+        "load" => {
+            let session = Session::load(path).expect("Failed to load session");
+            let maps = session.restore(&graph, Duration::from_secs(10));
+            println!("Restored {} of {} maps", maps.len(), session.maps.len());
+            // keep polling so the maps are announced to the network
+            graph.poll_and_block(Duration::from_secs(1));
+        }
+        _ => eprintln!("unknown command '{}'", command),
+    }
+}
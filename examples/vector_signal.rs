@@ -13,7 +13,8 @@ fn main() {
 
     println!("Device became ready!");
     let mut sig = dev.create_vector_signal::<f64>("test_sin", mpr_dir::MPR_DIR_OUT, 2);
-    let debug_sig = dev.create_vector_signal::<f64>("debug_msg", mpr_dir::MPR_DIR_IN, 2);
+    // the vector length can also be given by an array type
+    let debug_sig = dev.create_signal::<[f64; 2]>("debug_msg", mpr_dir::MPR_DIR_IN);
     loop {
         dev.poll_and_block(Duration::from_millis(100));
         let time = (SystemTime::now()
@@ -27,7 +28,7 @@ fn main() {
         if debug_sig.get_status().was_set_remote() {
             println!(
                 "Received debug message: {:?}",
-                debug_sig.get_value::<f64>().unwrap().0
+                debug_sig.get_value_as::<[f64; 2]>().unwrap().0
             );
         }
     }
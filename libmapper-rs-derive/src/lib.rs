@@ -0,0 +1,174 @@
+//! # libmapper-rs-derive
+//!
+//! Derive macros for [libmapper-rs](https://crates.io/crates/libmapper-rs).
+//! Enable the `derive` feature of libmapper-rs instead of depending on this crate directly.
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, spanned::Spanned, Data, DeriveInput, Expr, Fields, Ident, LitStr, Type};
+
+/// Derive `libmapper_rs::signal::MapperSignals` for a struct of plain values.
+///
+/// Every field annotated with `#[signal(..)]` becomes a signal on the device passed to `create_signals`.
+/// A companion struct named `<Struct>Signals` is generated, holding one `Signal` per annotated field.
+///
+/// # Attributes
+/// - `dir = "in" | "out"` (required): the direction of the signal.
+/// - `name = "..."`: the name of the signal, defaults to the field name.
+/// - `unit = "..."`: the unit of the signal.
+/// - `min = <expr>`, `max = <expr>`: the range of the signal, with the same type as the field.
+#[proc_macro_derive(MapperSignals, attributes(signal))]
+pub fn derive_mapper_signals(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  match expand(input) {
+    Ok(tokens) => tokens.into(),
+    Err(e) => e.to_compile_error().into()
+  }
+}
+
+/// A struct field annotated with `#[signal(..)]`.
+struct SignalField {
+  ident: Ident,
+  ty: Type,
+  name: LitStr,
+  output: bool,
+  unit: Option<LitStr>,
+  min: Option<Expr>,
+  max: Option<Expr>
+}
+
+fn parse_field(field: &syn::Field) -> syn::Result<Option<SignalField>> {
+  let Some(attr) = field.attrs.iter().find(|a| a.path().is_ident("signal")) else {
+    return Ok(None);
+  };
+  let ident = field.ident.clone().ok_or_else(|| syn::Error::new(field.span(), "MapperSignals fields must be named"))?;
+
+  let mut name = None;
+  let mut direction: Option<LitStr> = None;
+  let mut unit = None;
+  let mut min = None;
+  let mut max = None;
+  attr.parse_nested_meta(|meta| {
+    if meta.path.is_ident("dir") {
+      direction = Some(meta.value()?.parse()?);
+    } else if meta.path.is_ident("name") {
+      name = Some(meta.value()?.parse()?);
+    } else if meta.path.is_ident("unit") {
+      unit = Some(meta.value()?.parse()?);
+    } else if meta.path.is_ident("min") {
+      min = Some(meta.value()?.parse()?);
+    } else if meta.path.is_ident("max") {
+      max = Some(meta.value()?.parse()?);
+    } else {
+      return Err(meta.error("expected one of `dir`, `name`, `unit`, `min` or `max`"));
+    }
+    Ok(())
+  })?;
+
+  let direction = direction.ok_or_else(|| syn::Error::new(attr.span(), "missing `dir = \"in\"` or `dir = \"out\"`"))?;
+  let output = match direction.value().as_str() {
+    "in" => false,
+    "out" => true,
+    _ => return Err(syn::Error::new(direction.span(), "`dir` must be \"in\" or \"out\""))
+  };
+
+  Ok(Some(SignalField {
+    name: name.unwrap_or_else(|| LitStr::new(&ident.to_string(), Span::call_site())),
+    ident,
+    ty: field.ty.clone(),
+    output,
+    unit,
+    min,
+    max
+  }))
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+  let Data::Struct(data) = &input.data else {
+    return Err(syn::Error::new(input.span(), "MapperSignals can only be derived for structs"));
+  };
+  let Fields::Named(fields) = &data.fields else {
+    return Err(syn::Error::new(input.span(), "MapperSignals requires a struct with named fields"));
+  };
+  let mut signals = Vec::new();
+  for field in &fields.named {
+    if let Some(signal) = parse_field(field)? {
+      signals.push(signal);
+    }
+  }
+
+  let ident = &input.ident;
+  let vis = &input.vis;
+  let signals_ident = format_ident!("{}Signals", ident);
+  let doc = format!("The signals of [{}], created by `MapperSignals::create_signals`.", ident);
+
+  let field_idents: Vec<_> = signals.iter().map(|s| &s.ident).collect();
+  // the signals are created as locals before building the companion struct, prefixed so that
+  // field names can't shadow the `device` parameter or each other's range values
+  let local_idents: Vec<_> = field_idents.iter().map(|ident| format_ident!("__mapper_signal_{}", ident)).collect();
+
+  let create = signals.iter().zip(&local_idents).map(|(s, signal)| {
+    let SignalField { ty, name, .. } = s;
+    let direction = if s.output { quote!(MPR_DIR_OUT) } else { quote!(MPR_DIR_IN) };
+    let unit = s.unit.as_ref().map(|unit| quote! {
+      ::libmapper_rs::object::MapperObject::try_set_property_str(&#signal, ::libmapper_rs::constants::mpr_prop::MPR_PROP_UNIT, #unit)?;
+    });
+    let min = s.min.as_ref().map(|min| quote! {
+      ::libmapper_rs::object::MapperObject::set_property_vec(&#signal, ::libmapper_rs::constants::mpr_prop::MPR_PROP_MIN,
+        <#ty as ::libmapper_rs::device::SignalType>::as_elements(&#min));
+    });
+    let max = s.max.as_ref().map(|max| quote! {
+      ::libmapper_rs::object::MapperObject::set_property_vec(&#signal, ::libmapper_rs::constants::mpr_prop::MPR_PROP_MAX,
+        <#ty as ::libmapper_rs::device::SignalType>::as_elements(&#max));
+    });
+    quote! {
+      let #signal = device.try_create_signal::<#ty>(#name, ::libmapper_rs::constants::mpr_dir::#direction)?;
+      #unit
+      #min
+      #max
+    }
+  });
+
+  let read = signals.iter().filter(|s| !s.output).map(|s| {
+    let SignalField { ident, ty, .. } = s;
+    quote! {
+      if let Ok((value, _)) = signals.#ident.get_value_as::<#ty>() {
+        self.#ident = value;
+      }
+    }
+  });
+
+  let write = signals.iter().filter(|s| s.output).map(|s| {
+    let SignalField { ident, ty, .. } = s;
+    quote! {
+      signals.#ident.set_value_as::<#ty>(&self.#ident)?;
+    }
+  });
+
+  Ok(quote! {
+    #[doc = #doc]
+    #vis struct #signals_ident {
+      #( pub #field_idents: ::libmapper_rs::signal::Signal, )*
+    }
+
+    impl ::libmapper_rs::signal::MapperSignals for #ident {
+      type Signals = #signals_ident;
+
+      fn create_signals(device: &::libmapper_rs::device::Device) -> ::std::result::Result<Self::Signals, ::libmapper_rs::Error> {
+        #( #create )*
+        Ok(#signals_ident {
+          #( #field_idents: #local_idents, )*
+        })
+      }
+
+      fn read_all(&mut self, signals: &Self::Signals) {
+        #( #read )*
+      }
+
+      fn write_all(&self, signals: &mut Self::Signals) -> ::std::result::Result<(), ::libmapper_rs::Error> {
+        #( #write )*
+        Ok(())
+      }
+    }
+  })
+}
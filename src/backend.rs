@@ -0,0 +1,14 @@
+//! The libmapper functions used by the rest of the crate.
+//!
+//! By default these are the C library's functions from [bindings](crate::bindings).
+//! With the `fake-backend` feature, they are replaced by an in-process simulation written in rust (see `backend/fake.rs`),
+//! so that code using this crate can be tested without libmapper installed and without network access.
+//!
+//! Types and constants always come from the bindings, only the functions are swapped.
+#[cfg(not(feature = "fake-backend"))]
+pub(crate) use crate::bindings::*;
+
+#[cfg(feature = "fake-backend")]
+mod fake;
+#[cfg(feature = "fake-backend")]
+pub(crate) use fake::*;
@@ -0,0 +1,1144 @@
+//! An in-process simulation of the parts of libmapper used by this crate.
+//!
+//! Every graph in the process sees the same simulated network, so devices created anywhere in a test can be discovered,
+//! mapped and polled as if they were on the local network. Objects are stored in a global table and their handles are
+//! (1-based) indices into it, so a handle from the fake backend must never be passed to the real library.
+//!
+//! What is simulated:
+//! - devices become ready the first time they are polled, and get an ordinal suffix (`name.1`, `name.2`, ...),
+//! - maps become ready once they have been pushed and all of their devices are ready,
+//! - values set on a source signal are passed through the map's expression and delivered to the destination
+//!   the next time the destination's device is polled, calling it's `MPR_SIG_UPDATE` handler,
+//! - expressions of the form `y=<expr>`, where `<expr>` uses `x`, numbers, `+ - * /` and parentheses.
+//!   Any other expression passes values through unchanged.
+//! - lists can be filtered by a single string property with `MPR_OP_EQ` or `MPR_OP_NEQ`, other filters panic.
+//!
+//! Functions that aren't simulated are not available with this backend at all, using one is a compile error.
+use std::{collections::BTreeMap, ffi::{c_char, c_int, c_void, CStr, CString}, mem, ptr, sync::{Mutex, MutexGuard}, thread, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+// only the types are shared with the real bindings, so a function this module doesn't simulate fails to compile
+// instead of silently calling into libmapper
+pub(crate) use crate::bindings::{mpr_dev, mpr_dir, mpr_graph, mpr_id, mpr_list, mpr_loc, mpr_map, mpr_obj, mpr_op, mpr_prop,
+  mpr_proto, mpr_sig, mpr_sig_evt, mpr_sig_handler, mpr_status, mpr_steal_type, mpr_time, mpr_type};
+
+/// Number of seconds between the NTP epoch (1900-01-01) and the unix epoch (1970-01-01).
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+
+/// Status flags cleared when the status of an instance is read.
+const TRANSIENT_STATUS: c_int = mpr_status::MPR_STATUS_UPDATE_LOC as c_int
+  | mpr_status::MPR_STATUS_UPDATE_REM as c_int
+  | mpr_status::MPR_STATUS_NEW_VALUE as c_int;
+
+static NETWORK: Mutex<Network> = Mutex::new(Network { objects: Vec::new(), next_id: 1 });
+
+/// The state of every simulated object.
+struct Network {
+  objects: Vec<Option<Object>>,
+  next_id: u64
+}
+
+struct Object {
+  /// Index of the graph this object was created in.
+  graph: usize,
+  kind: Kind,
+  props: Vec<Prop>
+}
+
+enum Kind {
+  Graph {
+    subscribed: bool,
+    /// Set for graphs created implicitly by `mpr_dev_new`, which are freed with their device.
+    private: bool
+  },
+  Device {
+    ready: bool,
+    time: Option<mpr_time>,
+    queue: Vec<Update>
+  },
+  Signal {
+    device: usize,
+    handler: mpr_sig_handler,
+    events: c_int,
+    instances: BTreeMap<mpr_id, Instance>
+  },
+  Map {
+    sources: Vec<usize>,
+    destinations: Vec<usize>,
+    pushed: bool,
+    ready: bool
+  }
+}
+
+#[derive(Default)]
+struct Instance {
+  /// The value, stored in the signal's data type.
+  value: Option<Vec<u8>>,
+  time: mpr_time,
+  status: c_int
+}
+
+/// A value travelling along a map, waiting for the destination device to be polled.
+struct Update {
+  signal: usize,
+  instance: mpr_id,
+  values: Vec<f64>,
+  time: mpr_time
+}
+
+struct Prop {
+  prop: mpr_prop,
+  key: CString,
+  data_type: mpr_type,
+  length: c_int,
+  value: Value,
+  published: bool
+}
+
+enum Value {
+  Bytes(Vec<u8>),
+  Str(CString),
+  /// Multiple strings, with the addresses of each string for handing out as a `const char**`.
+  Strs {
+    // only read through `ptrs`
+    _strings: Vec<CString>,
+    ptrs: Vec<usize>
+  },
+  Ptr(usize)
+}
+
+impl Value {
+  fn as_ptr(&self) -> *const c_void {
+    match self {
+      Value::Bytes(bytes) => bytes.as_ptr() as *const c_void,
+      Value::Str(s) => s.as_ptr() as *const c_void,
+      Value::Strs { ptrs, .. } => ptrs.as_ptr() as *const c_void,
+      Value::Ptr(p) => *p as *const c_void
+    }
+  }
+}
+
+fn network() -> MutexGuard<'static, Network> {
+  NETWORK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn to_handle(index: usize) -> *mut c_void {
+  (index + 1) as *mut c_void
+}
+
+fn from_handle(handle: *const c_void) -> Option<usize> {
+  (handle as usize).checked_sub(1)
+}
+
+fn now() -> mpr_time {
+  let since_unix = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+  let sec = since_unix.as_secs() + NTP_UNIX_OFFSET;
+  let frac = ((since_unix.subsec_nanos() as u64) << 32) / 1_000_000_000;
+  from_fixed((sec << 32) | frac)
+}
+
+/// Convert a timetag (`{ sec, frac }` in memory) to 32.32 fixed point.
+fn to_fixed(time: mpr_time) -> u64 {
+  time.rotate_right(32)
+}
+
+fn from_fixed(fixed: u64) -> mpr_time {
+  fixed.rotate_left(32)
+}
+
+fn key_of(prop: mpr_prop) -> &'static str {
+  match prop {
+    mpr_prop::MPR_PROP_DATA => "data",
+    mpr_prop::MPR_PROP_DEV => "device",
+    mpr_prop::MPR_PROP_DIR => "direction",
+    mpr_prop::MPR_PROP_EXPR => "expr",
+    mpr_prop::MPR_PROP_ID => "id",
+    mpr_prop::MPR_PROP_IS_LOCAL => "is_local",
+    mpr_prop::MPR_PROP_LEN => "length",
+    mpr_prop::MPR_PROP_MAX => "max",
+    mpr_prop::MPR_PROP_MIN => "min",
+    mpr_prop::MPR_PROP_MUTED => "muted",
+    mpr_prop::MPR_PROP_NAME => "name",
+    mpr_prop::MPR_PROP_NUM_INST => "num_inst",
+    mpr_prop::MPR_PROP_ORDINAL => "ordinal",
+    mpr_prop::MPR_PROP_PROCESS_LOC => "process_loc",
+    mpr_prop::MPR_PROP_PROTOCOL => "protocol",
+    mpr_prop::MPR_PROP_STEAL_MODE => "steal",
+    mpr_prop::MPR_PROP_TYPE => "type",
+    mpr_prop::MPR_PROP_UNIT => "unit",
+    mpr_prop::MPR_PROP_VERSION => "version",
+    _ => "unknown"
+  }
+}
+
+/// Size in bytes of one element of a numeric type.
+fn size_of_type(data_type: mpr_type) -> usize {
+  match data_type {
+    mpr_type::MPR_DBL | mpr_type::MPR_INT64 | mpr_type::MPR_TIME => 8,
+    _ => 4
+  }
+}
+
+/// Read `length` numeric values of `data_type` as `f64`.
+unsafe fn read_f64s(data_type: mpr_type, length: usize, value: *const c_void) -> Vec<f64> {
+  (0..length).map(|i| match data_type {
+    mpr_type::MPR_DBL => ptr::read_unaligned((value as *const f64).add(i)),
+    mpr_type::MPR_FLT => ptr::read_unaligned((value as *const f32).add(i)) as f64,
+    mpr_type::MPR_INT64 => ptr::read_unaligned((value as *const i64).add(i)) as f64,
+    _ => ptr::read_unaligned((value as *const i32).add(i)) as f64
+  }).collect()
+}
+
+/// Encode values as bytes of `data_type`.
+fn write_f64s(data_type: mpr_type, values: &[f64]) -> Vec<u8> {
+  values.iter().flat_map(|v| match data_type {
+    mpr_type::MPR_DBL => v.to_ne_bytes().to_vec(),
+    mpr_type::MPR_FLT => (*v as f32).to_ne_bytes().to_vec(),
+    mpr_type::MPR_INT64 => (*v as i64).to_ne_bytes().to_vec(),
+    _ => (*v as i32).to_ne_bytes().to_vec()
+  }).collect()
+}
+
+impl Object {
+  fn data_type(&self) -> mpr_type {
+    match self.kind {
+      Kind::Graph { .. } => mpr_type::MPR_GRAPH,
+      Kind::Device { .. } => mpr_type::MPR_DEV,
+      Kind::Signal { .. } => mpr_type::MPR_SIG,
+      Kind::Map { .. } => mpr_type::MPR_MAP
+    }
+  }
+
+  /// The type of a signal's values. Anything other than a numeric type in `MPR_PROP_TYPE` is read as `MPR_FLT`.
+  fn value_type(&self) -> mpr_type {
+    let numeric = [mpr_type::MPR_INT32, mpr_type::MPR_INT64, mpr_type::MPR_FLT, mpr_type::MPR_DBL];
+    self.get_f64(mpr_prop::MPR_PROP_TYPE)
+      .and_then(|t| numeric.into_iter().find(|n| *n as i32 as f64 == t))
+      .unwrap_or(mpr_type::MPR_FLT)
+  }
+
+  fn find(&self, prop: mpr_prop, key: Option<&CStr>) -> Option<&Prop> {
+    match (prop, key) {
+      (mpr_prop::MPR_PROP_EXTRA | mpr_prop::MPR_PROP_UNKNOWN, Some(key)) => self.props.iter().find(|p| p.key.as_c_str() == key),
+      (mpr_prop::MPR_PROP_UNKNOWN, None) => None,
+      _ => self.props.iter().find(|p| p.prop == prop)
+    }
+  }
+
+  fn set(&mut self, prop: mpr_prop, key: &CStr, data_type: mpr_type, length: c_int, value: Value, published: bool) {
+    let new = Prop {
+      prop,
+      key: key.to_owned(),
+      data_type,
+      length,
+      value,
+      published
+    };
+    match self.props.iter_mut().find(|p| p.prop == prop && p.key.as_c_str() == key) {
+      Some(existing) => *existing = new,
+      None => self.props.push(new)
+    }
+  }
+
+  fn set_standard(&mut self, prop: mpr_prop, data_type: mpr_type, value: Value) {
+    let key = CString::new(key_of(prop)).unwrap();
+    self.set(prop, &key, data_type, 1, value, true);
+  }
+
+  fn set_int32(&mut self, prop: mpr_prop, value: i32) {
+    self.set_standard(prop, mpr_type::MPR_INT32, Value::Bytes(value.to_ne_bytes().to_vec()));
+  }
+
+  fn set_bool(&mut self, prop: mpr_prop, value: bool) {
+    self.set_standard(prop, mpr_type::MPR_BOOL, Value::Bytes((value as i32).to_ne_bytes().to_vec()));
+  }
+
+  fn set_str(&mut self, prop: mpr_prop, value: &str) {
+    self.set_standard(prop, mpr_type::MPR_STR, Value::Str(CString::new(value).unwrap_or_default()));
+  }
+
+  fn get_f64(&self, prop: mpr_prop) -> Option<f64> {
+    let p = self.find(prop, None)?;
+    match &p.value {
+      Value::Bytes(bytes) if !bytes.is_empty() => Some(unsafe { read_f64s(p.data_type, 1, bytes.as_ptr() as *const c_void)[0] }),
+      Value::Ptr(v) => Some(*v as f64),
+      _ => None
+    }
+  }
+
+  fn get_str(&self, prop: mpr_prop) -> Option<&str> {
+    match &self.find(prop, None)?.value {
+      Value::Str(s) => s.to_str().ok(),
+      _ => None
+    }
+  }
+}
+
+impl Network {
+  fn get(&self, handle: *const c_void) -> Option<&Object> {
+    self.objects.get(from_handle(handle)?)?.as_ref()
+  }
+
+  fn get_mut(&mut self, handle: *const c_void) -> Option<&mut Object> {
+    self.objects.get_mut(from_handle(handle)?)?.as_mut()
+  }
+
+  fn add(&mut self, graph: Option<usize>, kind: Kind) -> usize {
+    let index = self.objects.len();
+    let mut object = Object {
+      graph: graph.unwrap_or(index),
+      kind,
+      props: Vec::new()
+    };
+    object.set_standard(mpr_prop::MPR_PROP_ID, mpr_type::MPR_INT64, Value::Bytes(self.next_id.to_ne_bytes().to_vec()));
+    self.next_id += 1;
+    self.objects.push(Some(object));
+    index
+  }
+
+  /// Remove an object, along with everything that depends on it.
+  fn remove(&mut self, index: usize) {
+    let Some(object) = self.objects.get_mut(index).and_then(Option::take) else {
+      return;
+    };
+    let dependents: Vec<usize> = self.objects.iter().enumerate().filter_map(|(i, o)| {
+      let o = o.as_ref()?;
+      let depends = match (&object.kind, &o.kind) {
+        (Kind::Graph { .. }, _) => o.graph == index,
+        (Kind::Device { .. }, Kind::Signal { device, .. }) => *device == index,
+        (Kind::Signal { .. }, Kind::Map { sources, destinations, .. }) => sources.contains(&index) || destinations.contains(&index),
+        _ => false
+      };
+      depends.then_some(i)
+    }).collect();
+    for dependent in dependents {
+      self.remove(dependent);
+    }
+    if let Kind::Device { .. } = object.kind {
+      if let Some(Object { kind: Kind::Graph { private: true, .. }, .. }) = self.objects.get(object.graph).and_then(Option::as_ref) {
+        self.remove(object.graph);
+      }
+    }
+  }
+
+  fn signal_device(&self, signal: usize) -> Option<usize> {
+    match self.objects.get(signal)?.as_ref()?.kind {
+      Kind::Signal { device, .. } => Some(device),
+      _ => None
+    }
+  }
+
+  fn device_is_ready(&self, device: usize) -> bool {
+    matches!(self.objects.get(device).and_then(Option::as_ref), Some(Object { kind: Kind::Device { ready: true, .. }, .. }))
+  }
+
+  /// Make every pushed map whose devices are ready active.
+  fn update_maps(&mut self) {
+    let ready: Vec<usize> = self.objects.iter().enumerate().filter_map(|(i, o)| match &o.as_ref()?.kind {
+      Kind::Map { sources, destinations, pushed: true, ready: false } => sources.iter().chain(destinations)
+        .all(|s| self.signal_device(*s).is_some_and(|d| self.device_is_ready(d)))
+        .then_some(i),
+      _ => None
+    }).collect();
+    for index in ready {
+      if let Some(Object { kind: Kind::Map { ready, .. }, .. }) = self.objects[index].as_mut() {
+        *ready = true;
+      }
+    }
+  }
+
+  /// Get the indices of every live map matching `filter`.
+  fn maps(&self, filter: impl Fn(&[usize], &[usize]) -> bool) -> Vec<usize> {
+    self.objects.iter().enumerate().filter_map(|(i, o)| match &o.as_ref()?.kind {
+      Kind::Map { sources, destinations, .. } if filter(sources, destinations) => Some(i),
+      _ => None
+    }).collect()
+  }
+
+  /// Send a new value of `signal` along every active map.
+  fn propagate(&mut self, signal: usize, instance: mpr_id, values: &[f64], time: mpr_time) {
+    for map in self.maps(|sources, _| sources.contains(&signal)) {
+      let map = self.objects[map].as_ref().unwrap();
+      let Kind::Map { destinations, ready: true, .. } = &map.kind else {
+        continue;
+      };
+      if map.get_f64(mpr_prop::MPR_PROP_MUTED).unwrap_or(0.0) != 0.0 {
+        continue;
+      }
+      let expression = map.get_str(mpr_prop::MPR_PROP_EXPR).and_then(Expr::parse);
+      let values: Vec<f64> = values.iter().map(|x| expression.as_ref().map_or(*x, |e| e.eval(*x))).collect();
+      for destination in destinations.clone() {
+        let Some(device) = self.signal_device(destination) else {
+          continue;
+        };
+        if let Some(Object { kind: Kind::Device { queue, .. }, .. }) = self.objects[device].as_mut() {
+          queue.push(Update {
+            signal: destination,
+            instance,
+            values: values.clone(),
+            time
+          });
+        }
+      }
+    }
+  }
+}
+
+/// A list of object handles. `current` must be the first field, as callers read it through the `mpr_list` pointer.
+#[repr(C)]
+struct List {
+  current: *mut c_void,
+  items: Vec<*mut c_void>,
+  next: usize
+}
+
+fn new_list(items: Vec<usize>) -> mpr_list {
+  if items.is_empty() {
+    return ptr::null_mut();
+  }
+  let items: Vec<*mut c_void> = items.into_iter().map(to_handle).collect();
+  let list = Box::new(List {
+    current: items[0],
+    items,
+    next: 1
+  });
+  Box::into_raw(list) as mpr_list
+}
+
+unsafe fn take_list(list: mpr_list) -> Vec<*mut c_void> {
+  if list.is_null() {
+    return Vec::new();
+  }
+  let list = Box::from_raw(list as *mut List);
+  list.items[list.next - 1..].to_vec()
+}
+
+/// A parsed map expression of the form `y=<expr>`.
+enum Expr {
+  X,
+  Number(f64),
+  Neg(Box<Expr>),
+  Binary(Box<Expr>, char, Box<Expr>)
+}
+
+impl Expr {
+  fn parse(expression: &str) -> Option<Expr> {
+    let source: Vec<char> = expression.chars().filter(|c| !c.is_whitespace()).collect();
+    let rest = source.strip_prefix(&['y', '='])?;
+    let mut position = 0;
+    let expr = Expr::parse_sum(rest, &mut position)?;
+    (position == rest.len()).then_some(expr)
+  }
+
+  fn parse_sum(source: &[char], position: &mut usize) -> Option<Expr> {
+    let mut left = Expr::parse_product(source, position)?;
+    while let Some(op @ ('+' | '-')) = source.get(*position).copied() {
+      *position += 1;
+      left = Expr::Binary(Box::new(left), op, Box::new(Expr::parse_product(source, position)?));
+    }
+    Some(left)
+  }
+
+  fn parse_product(source: &[char], position: &mut usize) -> Option<Expr> {
+    let mut left = Expr::parse_atom(source, position)?;
+    while let Some(op @ ('*' | '/')) = source.get(*position).copied() {
+      *position += 1;
+      left = Expr::Binary(Box::new(left), op, Box::new(Expr::parse_atom(source, position)?));
+    }
+    Some(left)
+  }
+
+  fn parse_atom(source: &[char], position: &mut usize) -> Option<Expr> {
+    match source.get(*position)? {
+      'x' => {
+        *position += 1;
+        Some(Expr::X)
+      },
+      '-' => {
+        *position += 1;
+        Some(Expr::Neg(Box::new(Expr::parse_atom(source, position)?)))
+      },
+      '(' => {
+        *position += 1;
+        let inner = Expr::parse_sum(source, position)?;
+        (source.get(*position) == Some(&')')).then(|| *position += 1)?;
+        Some(inner)
+      },
+      _ => {
+        let start = *position;
+        while source.get(*position).is_some_and(|c| c.is_ascii_digit() || *c == '.') {
+          *position += 1;
+        }
+        source[start..*position].iter().collect::<String>().parse().ok().map(Expr::Number)
+      }
+    }
+  }
+
+  fn eval(&self, x: f64) -> f64 {
+    match self {
+      Expr::X => x,
+      Expr::Number(n) => *n,
+      Expr::Neg(e) => -e.eval(x),
+      Expr::Binary(l, op, r) => {
+        let (l, r) = (l.eval(x), r.eval(x));
+        match op {
+          '+' => l + r,
+          '-' => l - r,
+          '*' => l * r,
+          _ => l / r
+        }
+      }
+    }
+  }
+}
+
+pub(crate) unsafe fn mpr_get_version() -> *const c_char {
+  c"2.4.9".as_ptr()
+}
+
+pub(crate) unsafe fn mpr_graph_new(autosubscribe_types: c_int) -> mpr_graph {
+  let index = network().add(None, Kind::Graph { subscribed: autosubscribe_types != 0, private: false });
+  to_handle(index)
+}
+
+pub(crate) unsafe fn mpr_graph_free(graph: mpr_graph) {
+  if let Some(index) = from_handle(graph) {
+    network().remove(index);
+  }
+}
+
+pub(crate) unsafe fn mpr_graph_poll(_graph: mpr_graph, block_ms: c_int) -> c_int {
+  network().update_maps();
+  if block_ms > 0 {
+    thread::sleep(Duration::from_millis(block_ms as u64));
+  }
+  0
+}
+
+/// Every simulated graph is already isolated from the real network, so the interface is ignored.
+pub(crate) unsafe fn mpr_graph_set_interface(_graph: mpr_graph, _interface: *const c_char) -> c_int {
+  0
+}
+
+pub(crate) unsafe fn mpr_graph_subscribe(graph: mpr_graph, _device: mpr_dev, _types: c_int, _timeout: c_int) {
+  if let Some(Object { kind: Kind::Graph { subscribed, .. }, .. }) = network().get_mut(graph) {
+    *subscribed = true;
+  }
+}
+
+pub(crate) unsafe fn mpr_graph_get_list(graph: mpr_graph, types: c_int) -> mpr_list {
+  let net = network();
+  let Some(graph) = from_handle(graph) else {
+    return ptr::null_mut();
+  };
+  let subscribed = matches!(net.objects.get(graph).and_then(Option::as_ref), Some(Object { kind: Kind::Graph { subscribed: true, .. }, .. }));
+  let items = net.objects.iter().enumerate().filter_map(|(i, o)| {
+    let o = o.as_ref()?;
+    if o.graph != graph && !subscribed {
+      return None;
+    }
+    let wanted = match &o.kind {
+      Kind::Device { .. } => types & mpr_type::MPR_DEV as c_int != 0,
+      Kind::Signal { .. } => {
+        let direction = o.get_f64(mpr_prop::MPR_PROP_DIR).unwrap_or(0.0) as c_int;
+        (direction & mpr_dir::MPR_DIR_IN as c_int != 0 && types & mpr_type::MPR_SIG_IN as c_int != 0)
+          || (direction & mpr_dir::MPR_DIR_OUT as c_int != 0 && types & mpr_type::MPR_SIG_OUT as c_int != 0)
+      },
+      Kind::Map { pushed, .. } => *pushed && types & mpr_type::MPR_MAP as c_int != 0,
+      Kind::Graph { .. } => false
+    };
+    wanted.then_some(i)
+  }).collect();
+  new_list(items)
+}
+
+pub(crate) unsafe fn mpr_dev_new(name: *const c_char, graph: mpr_graph) -> mpr_dev {
+  let mut net = network();
+  let graph = match from_handle(graph) {
+    Some(graph) if net.objects.get(graph).is_some_and(Option::is_some) => graph,
+    _ => net.add(None, Kind::Graph { subscribed: false, private: true })
+  };
+  let name = CStr::from_ptr(name).to_string_lossy().into_owned();
+  let ordinal = 1 + net.objects.iter().flatten()
+    .filter(|o| matches!(o.kind, Kind::Device { .. }))
+    .filter_map(|o| o.get_str(mpr_prop::MPR_PROP_NAME)?.rsplit_once('.').filter(|(base, _)| *base == name)?.1.parse::<i32>().ok())
+    .max().unwrap_or(0);
+
+  let index = net.add(Some(graph), Kind::Device { ready: false, time: None, queue: Vec::new() });
+  let device = net.objects[index].as_mut().unwrap();
+  device.set_str(mpr_prop::MPR_PROP_NAME, &format!("{}.{}", name, ordinal));
+  device.set_int32(mpr_prop::MPR_PROP_ORDINAL, ordinal);
+  device.set_bool(mpr_prop::MPR_PROP_IS_LOCAL, true);
+  to_handle(index)
+}
+
+pub(crate) unsafe fn mpr_dev_free(device: mpr_dev) {
+  if let Some(index) = from_handle(device) {
+    network().remove(index);
+  }
+}
+
+pub(crate) unsafe fn mpr_dev_poll(device: mpr_dev, block_ms: c_int) -> c_int {
+  let updates = {
+    let mut net = network();
+    let updates = match net.get_mut(device) {
+      Some(Object { kind: Kind::Device { ready, queue, .. }, .. }) => {
+        *ready = true;
+        mem::take(queue)
+      },
+      _ => Vec::new()
+    };
+    net.update_maps();
+    updates
+  };
+
+  let count = updates.len() as c_int;
+  for update in updates {
+    // the handler is called without holding the lock, as it may call back into the backend
+    let call = {
+      let mut net = network();
+      let Some(Some(signal)) = net.objects.get_mut(update.signal) else {
+        continue;
+      };
+      let data_type = signal.value_type();
+      let length = signal.get_f64(mpr_prop::MPR_PROP_LEN).unwrap_or(1.0) as usize;
+      let Kind::Signal { handler, events, instances, .. } = &mut signal.kind else {
+        continue;
+      };
+      let mut values = update.values;
+      values.resize(length, values.last().copied().unwrap_or(0.0));
+      let instance = instances.entry(update.instance).or_default();
+      instance.value = Some(write_f64s(data_type, &values));
+      instance.time = update.time;
+      instance.status |= mpr_status::MPR_STATUS_UPDATE_REM as c_int | mpr_status::MPR_STATUS_NEW_VALUE as c_int
+        | mpr_status::MPR_STATUS_HAS_VALUE as c_int | mpr_status::MPR_STATUS_ACTIVE as c_int;
+      let value = instance.value.as_ref().unwrap().as_ptr() as *const c_void;
+      match handler {
+        Some(handler) if *events & mpr_sig_evt::MPR_SIG_UPDATE as c_int != 0 => Some((*handler, data_type, length, value, update.time)),
+        _ => None
+      }
+    };
+    if let Some((handler, data_type, length, value, time)) = call {
+      handler(to_handle(update.signal), mpr_sig_evt::MPR_SIG_UPDATE, update.instance, length as c_int, data_type, value, time);
+    }
+  }
+
+  if block_ms > 0 {
+    thread::sleep(Duration::from_millis(block_ms as u64));
+  }
+  count
+}
+
+pub(crate) unsafe fn mpr_dev_get_is_ready(device: mpr_dev) -> c_int {
+  from_handle(device).is_some_and(|d| network().device_is_ready(d)) as c_int
+}
+
+pub(crate) unsafe fn mpr_dev_get_sigs(device: mpr_dev, direction: mpr_dir) -> mpr_list {
+  let net = network();
+  let Some(device) = from_handle(device) else {
+    return ptr::null_mut();
+  };
+  let items = net.objects.iter().enumerate().filter_map(|(i, o)| {
+    let o = o.as_ref()?;
+    let signal_direction = o.get_f64(mpr_prop::MPR_PROP_DIR).unwrap_or(0.0) as c_int;
+    match o.kind {
+      Kind::Signal { device: d, .. } if d == device && signal_direction & direction as c_int != 0 => Some(i),
+      _ => None
+    }
+  }).collect();
+  new_list(items)
+}
+
+pub(crate) unsafe fn mpr_dev_get_maps(device: mpr_dev, direction: mpr_dir) -> mpr_list {
+  let net = network();
+  let Some(device) = from_handle(device) else {
+    return ptr::null_mut();
+  };
+  let on_device = |signals: &[usize]| signals.iter().any(|s| net.signal_device(*s) == Some(device));
+  let items = net.maps(|sources, destinations| {
+    (direction as c_int & mpr_dir::MPR_DIR_OUT as c_int != 0 && on_device(sources))
+      || (direction as c_int & mpr_dir::MPR_DIR_IN as c_int != 0 && on_device(destinations))
+  });
+  new_list(items)
+}
+
+pub(crate) unsafe fn mpr_dev_get_time(device: mpr_dev) -> mpr_time {
+  match network().get(device) {
+    Some(Object { kind: Kind::Device { time: Some(time), .. }, .. }) => *time,
+    _ => now()
+  }
+}
+
+pub(crate) unsafe fn mpr_dev_set_time(device: mpr_dev, time: mpr_time) {
+  if let Some(Object { kind: Kind::Device { time: t, .. }, .. }) = network().get_mut(device) {
+    *t = Some(time);
+  }
+}
+
+pub(crate) unsafe fn mpr_dev_update_maps(_device: mpr_dev) {
+  // values are sent as soon as they are set
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) unsafe fn mpr_sig_new(parent: mpr_dev, direction: mpr_dir, name: *const c_char, length: c_int, type_: mpr_type,
+  unit: *const c_char, minimum: *const c_void, maximum: *const c_void, num_instances: *mut c_int,
+  handler: mpr_sig_handler, events: c_int) -> mpr_sig {
+  let mut net = network();
+  let Some(device) = from_handle(parent).filter(|d| matches!(net.objects.get(*d), Some(Some(Object { kind: Kind::Device { .. }, .. })))) else {
+    return ptr::null_mut();
+  };
+  let graph = net.objects[device].as_ref().unwrap().graph;
+  let index = net.add(Some(graph), Kind::Signal { device, handler, events, instances: BTreeMap::new() });
+  let signal = net.objects[index].as_mut().unwrap();
+  signal.set_str(mpr_prop::MPR_PROP_NAME, &CStr::from_ptr(name).to_string_lossy());
+  signal.set_int32(mpr_prop::MPR_PROP_DIR, direction as i32);
+  signal.set_int32(mpr_prop::MPR_PROP_LEN, length);
+  signal.set_standard(mpr_prop::MPR_PROP_TYPE, mpr_type::MPR_TYPE, Value::Bytes((type_ as u32).to_ne_bytes().to_vec()));
+  signal.set_int32(mpr_prop::MPR_PROP_NUM_INST, if num_instances.is_null() { 1 } else { *num_instances });
+  signal.set_int32(mpr_prop::MPR_PROP_STEAL_MODE, 0);
+  signal.set_bool(mpr_prop::MPR_PROP_IS_LOCAL, true);
+  if !unit.is_null() {
+    signal.set_str(mpr_prop::MPR_PROP_UNIT, &CStr::from_ptr(unit).to_string_lossy());
+  }
+  for (prop, value) in [(mpr_prop::MPR_PROP_MIN, minimum), (mpr_prop::MPR_PROP_MAX, maximum)] {
+    if !value.is_null() {
+      let bytes = std::slice::from_raw_parts(value as *const u8, length.max(0) as usize * size_of_type(type_)).to_vec();
+      let key = CString::new(key_of(prop)).unwrap();
+      signal.set(prop, &key, type_, length, Value::Bytes(bytes), true);
+    }
+  }
+  to_handle(index)
+}
+
+pub(crate) unsafe fn mpr_sig_free(signal: mpr_sig) {
+  if let Some(index) = from_handle(signal) {
+    network().remove(index);
+  }
+}
+
+pub(crate) unsafe fn mpr_sig_set_value(signal: mpr_sig, instance: mpr_id, length: c_int, type_: mpr_type, value: *const c_void) {
+  if value.is_null() {
+    return;
+  }
+  let values = read_f64s(type_, length.max(0) as usize, value);
+  let mut net = network();
+  let Some(index) = from_handle(signal) else {
+    return;
+  };
+  let Some(device) = net.signal_device(index) else {
+    return;
+  };
+  let time = match &net.objects[device].as_ref().unwrap().kind {
+    Kind::Device { time: Some(time), .. } => *time,
+    _ => now()
+  };
+  let object = net.objects[index].as_mut().unwrap();
+  let data_type = object.value_type();
+  let signal_length = object.get_f64(mpr_prop::MPR_PROP_LEN).unwrap_or(1.0) as usize;
+  if let Kind::Signal { instances, .. } = &mut object.kind {
+    let mut stored = values.clone();
+    stored.resize(signal_length, stored.last().copied().unwrap_or(0.0));
+    let state = instances.entry(instance).or_default();
+    state.value = Some(write_f64s(data_type, &stored));
+    state.time = time;
+    state.status |= mpr_status::MPR_STATUS_UPDATE_LOC as c_int | mpr_status::MPR_STATUS_NEW_VALUE as c_int
+      | mpr_status::MPR_STATUS_HAS_VALUE as c_int | mpr_status::MPR_STATUS_ACTIVE as c_int;
+  }
+  net.propagate(index, instance, &values, time);
+}
+
+pub(crate) unsafe fn mpr_sig_get_value(signal: mpr_sig, instance: mpr_id, time: *mut mpr_time) -> *const c_void {
+  let net = network();
+  let Some(Object { kind: Kind::Signal { instances, .. }, .. }) = net.get(signal) else {
+    return ptr::null();
+  };
+  match instances.get(&instance) {
+    Some(Instance { value: Some(value), time: t, .. }) => {
+      if !time.is_null() {
+        *time = *t;
+      }
+      value.as_ptr() as *const c_void
+    },
+    _ => ptr::null()
+  }
+}
+
+pub(crate) unsafe fn mpr_sig_set_cb(signal: mpr_sig, handler: mpr_sig_handler, events: c_int) {
+  if let Some(Object { kind: Kind::Signal { handler: h, events: e, .. }, .. }) = network().get_mut(signal) {
+    *h = handler;
+    *e = events;
+  }
+}
+
+pub(crate) unsafe fn mpr_sig_get_dev(signal: mpr_sig) -> mpr_dev {
+  from_handle(signal).and_then(|s| network().signal_device(s)).map_or(ptr::null_mut(), to_handle)
+}
+
+pub(crate) unsafe fn mpr_sig_get_maps(signal: mpr_sig, direction: mpr_dir) -> mpr_list {
+  let Some(signal) = from_handle(signal) else {
+    return ptr::null_mut();
+  };
+  let items = network().maps(|sources, destinations| {
+    (direction as c_int & mpr_dir::MPR_DIR_OUT as c_int != 0 && sources.contains(&signal))
+      || (direction as c_int & mpr_dir::MPR_DIR_IN as c_int != 0 && destinations.contains(&signal))
+  });
+  new_list(items)
+}
+
+pub(crate) unsafe fn mpr_sig_get_inst_status(signal: mpr_sig, instance: mpr_id) -> c_int {
+  match network().get_mut(signal) {
+    Some(Object { kind: Kind::Signal { instances, .. }, .. }) => instances.get_mut(&instance).map_or(0, |i| {
+      let status = i.status;
+      i.status &= !TRANSIENT_STATUS;
+      status
+    }),
+    _ => 0
+  }
+}
+
+pub(crate) unsafe fn mpr_sig_reserve_inst(signal: mpr_sig, number: c_int, _ids: *mut mpr_id, _data: *mut *mut c_void) -> c_int {
+  let mut net = network();
+  let Some(object) = net.get_mut(signal) else {
+    return 0;
+  };
+  let count = object.get_f64(mpr_prop::MPR_PROP_NUM_INST).unwrap_or(1.0) as i32;
+  object.set_int32(mpr_prop::MPR_PROP_NUM_INST, count + number.max(0));
+  number.max(0)
+}
+
+pub(crate) unsafe fn mpr_map_new(num_sources: c_int, sources: *const mpr_sig, num_destinations: c_int, destinations: *const mpr_sig) -> mpr_map {
+  let read = |signals: *const mpr_sig, count: c_int| -> Option<Vec<usize>> {
+    (0..count.max(0) as usize).map(|i| from_handle(*signals.add(i))).collect()
+  };
+  let (Some(sources), Some(destinations)) = (read(sources, num_sources), read(destinations, num_destinations)) else {
+    return ptr::null_mut();
+  };
+  let mut net = network();
+  if sources.is_empty() || destinations.is_empty() || sources.iter().chain(&destinations).any(|s| net.signal_device(*s).is_none()) {
+    return ptr::null_mut();
+  }
+  if let Some(existing) = net.maps(|s, d| s == sources.as_slice() && d == destinations.as_slice()).first() {
+    return to_handle(*existing);
+  }
+  let graph = net.objects[destinations[0]].as_ref().unwrap().graph;
+  let index = net.add(Some(graph), Kind::Map { sources, destinations, pushed: false, ready: false });
+  let map = net.objects[index].as_mut().unwrap();
+  map.set_str(mpr_prop::MPR_PROP_EXPR, "y=x");
+  map.set_bool(mpr_prop::MPR_PROP_MUTED, false);
+  map.set_int32(mpr_prop::MPR_PROP_PROTOCOL, mpr_proto::MPR_PROTO_UDP as i32);
+  map.set_int32(mpr_prop::MPR_PROP_PROCESS_LOC, mpr_loc::MPR_LOC_SRC as i32);
+  map.set_bool(mpr_prop::MPR_PROP_IS_LOCAL, true);
+  to_handle(index)
+}
+
+pub(crate) unsafe fn mpr_map_release(map: mpr_map) {
+  if let Some(index) = from_handle(map) {
+    network().remove(index);
+  }
+}
+
+pub(crate) unsafe fn mpr_map_get_sigs(map: mpr_map, endpoint: mpr_loc) -> mpr_list {
+  let net = network();
+  let Some(Object { kind: Kind::Map { sources, destinations, .. }, .. }) = net.get(map) else {
+    return ptr::null_mut();
+  };
+  let mut items = Vec::new();
+  if endpoint as c_int & mpr_loc::MPR_LOC_SRC as c_int != 0 {
+    items.extend(sources);
+  }
+  if endpoint as c_int & mpr_loc::MPR_LOC_DST as c_int != 0 {
+    items.extend(destinations);
+  }
+  new_list(items)
+}
+
+pub(crate) unsafe fn mpr_map_get_is_ready(map: mpr_map) -> c_int {
+  matches!(network().get(map), Some(Object { kind: Kind::Map { ready: true, .. }, .. })) as c_int
+}
+
+pub(crate) unsafe fn mpr_obj_get_type(object: mpr_obj) -> mpr_type {
+  network().get(object).map_or(mpr_type::MPR_NULL, Object::data_type)
+}
+
+pub(crate) unsafe fn mpr_obj_get_graph(object: mpr_obj) -> mpr_graph {
+  network().get(object).map_or(ptr::null_mut(), |o| to_handle(o.graph))
+}
+
+pub(crate) unsafe fn mpr_obj_push(object: mpr_obj) {
+  let mut net = network();
+  if let Some(Object { kind: Kind::Map { pushed, .. }, .. }) = net.get_mut(object) {
+    *pushed = true;
+  }
+}
+
+pub(crate) unsafe fn mpr_obj_get_num_props(object: mpr_obj, _staged: c_int) -> c_int {
+  network().get(object).map_or(0, |o| o.props.len() as c_int)
+}
+
+/// Write the outputs of a property lookup.
+unsafe fn read_prop(prop: Option<&Prop>, key: *mut *const c_char, length: *mut c_int, type_: *mut mpr_type,
+  value: *mut *const c_void, published: *mut c_int) -> mpr_prop {
+  let Some(prop) = prop else {
+    if !value.is_null() {
+      *value = ptr::null();
+    }
+    return mpr_prop::MPR_PROP_UNKNOWN;
+  };
+  if !key.is_null() {
+    *key = prop.key.as_ptr();
+  }
+  if !length.is_null() {
+    *length = prop.length;
+  }
+  if !type_.is_null() {
+    *type_ = prop.data_type;
+  }
+  if !value.is_null() {
+    *value = prop.value.as_ptr();
+  }
+  if !published.is_null() {
+    *published = prop.published as c_int;
+  }
+  prop.prop
+}
+
+pub(crate) unsafe fn mpr_obj_get_prop_by_idx(object: mpr_obj, index: c_int, key: *mut *const c_char, length: *mut c_int,
+  type_: *mut mpr_type, value: *mut *const c_void, published: *mut c_int) -> mpr_prop {
+  let net = network();
+  let object = net.get(object);
+  // like libmapper, indices of 256 and above are `mpr_prop` values
+  let prop = object.and_then(|o| if index >= mpr_prop::MPR_PROP_BUNDLE as c_int {
+    o.props.iter().find(|p| p.prop as c_int == index & 0xFF00)
+  } else {
+    o.props.get(index.max(0) as usize)
+  });
+  read_prop(prop, key, length, type_, value, published)
+}
+
+pub(crate) unsafe fn mpr_obj_get_prop_by_key(object: mpr_obj, key: *const c_char, length: *mut c_int, type_: *mut mpr_type,
+  value: *mut *const c_void, published: *mut c_int) -> mpr_prop {
+  let net = network();
+  let key = CStr::from_ptr(key);
+  let prop = net.get(object).and_then(|o| o.props.iter().find(|p| p.key.as_c_str() == key));
+  read_prop(prop, ptr::null_mut(), length, type_, value, published)
+}
+
+pub(crate) unsafe fn mpr_obj_get_prop_as_int32(object: mpr_obj, property: mpr_prop, key: *const c_char) -> c_int {
+  let net = network();
+  let key = (!key.is_null()).then(|| CStr::from_ptr(key));
+  net.get(object).and_then(|o| o.find(property, key)).map_or(0, |p| match &p.value {
+    Value::Bytes(bytes) if !bytes.is_empty() => read_f64s(p.data_type, 1, bytes.as_ptr() as *const c_void)[0] as c_int,
+    _ => 0
+  })
+}
+
+pub(crate) unsafe fn mpr_obj_get_prop_as_ptr(object: mpr_obj, property: mpr_prop, key: *const c_char) -> *const c_void {
+  let net = network();
+  let key = (!key.is_null()).then(|| CStr::from_ptr(key));
+  match net.get(object).and_then(|o| o.find(property, key)) {
+    Some(Prop { value: Value::Ptr(p), .. }) => *p as *const c_void,
+    _ => ptr::null()
+  }
+}
+
+pub(crate) unsafe fn mpr_obj_set_prop(object: mpr_obj, property: mpr_prop, key: *const c_char, length: c_int, type_: mpr_type,
+  value: *const c_void, publish: c_int) -> mpr_prop {
+  let mut net = network();
+  let Some(object) = net.get_mut(object) else {
+    return mpr_prop::MPR_PROP_UNKNOWN;
+  };
+  let key = match property {
+    mpr_prop::MPR_PROP_EXTRA | mpr_prop::MPR_PROP_UNKNOWN if key.is_null() => return mpr_prop::MPR_PROP_UNKNOWN,
+    mpr_prop::MPR_PROP_EXTRA | mpr_prop::MPR_PROP_UNKNOWN => CStr::from_ptr(key).to_owned(),
+    _ => CString::new(key_of(property)).unwrap()
+  };
+  let property = if property == mpr_prop::MPR_PROP_UNKNOWN { mpr_prop::MPR_PROP_EXTRA } else { property };
+  let length = length.max(0);
+  let stored = match type_ {
+    mpr_type::MPR_PTR => Value::Ptr(value as usize),
+    mpr_type::MPR_STR if length == 1 => Value::Str(CStr::from_ptr(value as *const c_char).to_owned()),
+    mpr_type::MPR_STR => {
+      let strings: Vec<CString> = (0..length as usize).map(|i| CStr::from_ptr(*(value as *const *const c_char).add(i)).to_owned()).collect();
+      let ptrs = strings.iter().map(|s| s.as_ptr() as usize).collect();
+      Value::Strs { _strings: strings, ptrs }
+    },
+    _ => Value::Bytes(std::slice::from_raw_parts(value as *const u8, length as usize * size_of_type(type_)).to_vec())
+  };
+  object.set(property, &key, type_, length, stored, publish != 0);
+  property
+}
+
+pub(crate) unsafe fn mpr_obj_remove_prop(object: mpr_obj, property: mpr_prop, key: *const c_char) -> c_int {
+  let mut net = network();
+  let Some(object) = net.get_mut(object) else {
+    return 0;
+  };
+  let key = (!key.is_null()).then(|| CStr::from_ptr(key));
+  let before = object.props.len();
+  object.props.retain(|p| match (property, key) {
+    (mpr_prop::MPR_PROP_EXTRA, Some(key)) => p.key.as_c_str() != key,
+    _ => p.prop != property
+  });
+  (object.props.len() != before) as c_int
+}
+
+pub(crate) unsafe fn mpr_list_get_next(list: mpr_list) -> mpr_list {
+  if list.is_null() {
+    return list;
+  }
+  let inner = &mut *(list as *mut List);
+  if inner.next < inner.items.len() {
+    inner.current = inner.items[inner.next];
+    inner.next += 1;
+    list
+  } else {
+    drop(Box::from_raw(list as *mut List));
+    ptr::null_mut()
+  }
+}
+
+pub(crate) unsafe fn mpr_list_free(list: mpr_list) {
+  if !list.is_null() {
+    drop(Box::from_raw(list as *mut List));
+  }
+}
+
+pub(crate) unsafe fn mpr_list_filter(list: mpr_list, property: mpr_prop, key: *const c_char, length: c_int, type_: mpr_type,
+  value: *const c_void, op: mpr_op) -> mpr_list {
+  if type_ != mpr_type::MPR_STR || length != 1 {
+    panic!("fake backend: unsupported list filter on {} values of {:?}, only single strings are supported", length, type_);
+  }
+  if !matches!(op, mpr_op::MPR_OP_EQ | mpr_op::MPR_OP_NEQ) {
+    panic!("fake backend: unsupported list filter {:?}, only MPR_OP_EQ and MPR_OP_NEQ are supported", op);
+  }
+  let wanted = CStr::from_ptr(value as *const c_char);
+  let key = (!key.is_null()).then(|| CStr::from_ptr(key));
+  let items = take_list(list);
+  let net = network();
+  let items = items.into_iter().filter_map(|i| from_handle(i)).filter(|i| {
+    let matches = net.objects[*i].as_ref()
+      .and_then(|o| o.find(property, key))
+      .is_some_and(|p| matches!(&p.value, Value::Str(s) if s.as_c_str() == wanted));
+    matches == (op == mpr_op::MPR_OP_EQ)
+  }).collect();
+  new_list(items)
+}
+
+pub(crate) unsafe fn mpr_time_as_dbl(time: mpr_time) -> f64 {
+  to_fixed(time) as f64 / 4_294_967_296.0
+}
+
+pub(crate) unsafe fn mpr_time_set_dbl(time: *mut mpr_time, value: f64) {
+  *time = from_fixed((value * 4_294_967_296.0) as u64);
+}
+
+pub(crate) unsafe fn mpr_time_add(augend: *mut mpr_time, addend: mpr_time) {
+  *augend = from_fixed(to_fixed(*augend).wrapping_add(to_fixed(addend)));
+}
+
+pub(crate) unsafe fn mpr_time_add_dbl(augend: *mut mpr_time, addend: f64) {
+  mpr_time_set_dbl(augend, mpr_time_as_dbl(*augend) + addend);
+}
+
+pub(crate) unsafe fn mpr_time_sub(minuend: *mut mpr_time, subtrahend: mpr_time) {
+  *minuend = from_fixed(to_fixed(*minuend).wrapping_sub(to_fixed(subtrahend)));
+}
+
+pub(crate) unsafe fn mpr_time_mul(time: *mut mpr_time, multiplicand: f64) {
+  mpr_time_set_dbl(time, mpr_time_as_dbl(*time) * multiplicand);
+}
+
+pub(crate) unsafe fn mpr_time_cmp(time1: mpr_time, time2: mpr_time) -> c_int {
+  to_fixed(time1).cmp(&to_fixed(time2)) as c_int
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::{Duration, Instant};
+
+  use super::*;
+  use crate::{device::Device, graph::Map, object::MapperObject, signal::Signal, util::read_list};
+
+  /// Names of the signals in `list`, consuming it.
+  fn names(list: mpr_list) -> Vec<String> {
+    let mut names = read_list(list, |sig| Signal::try_from_unowned(sig).unwrap().get_property_str(mpr_prop::MPR_PROP_NAME).unwrap());
+    names.sort();
+    names
+  }
+
+  /// Filter the signals of `device` by name.
+  fn filter(device: &Device, name: &CStr, op: mpr_op) -> Vec<String> {
+    unsafe {
+      let list = mpr_dev_get_sigs(device.handle, mpr_dir::MPR_DIR_ANY);
+      names(mpr_list_filter(list, mpr_prop::MPR_PROP_NAME, ptr::null(), 1, mpr_type::MPR_STR, name.as_ptr() as *const c_void, op))
+    }
+  }
+
+  #[test]
+  fn map_propagates_values() {
+    let src = Device::create("fake_test_src");
+    let dst = Device::create("fake_test_dst");
+    let mut output = src.create_signal::<f32>("out", mpr_dir::MPR_DIR_OUT);
+    let input = dst.create_signal::<f32>("in", mpr_dir::MPR_DIR_IN);
+    let map = Map::create(&output, &input);
+    map.set_expr("y=(x+1)*2");
+    map.push();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !map.is_ready() {
+      assert!(Instant::now() < deadline, "map did not become ready");
+      src.poll_and_block(Duration::from_millis(5));
+      dst.poll_and_block(Duration::from_millis(5));
+    }
+
+    output.set_value_scalar(&3.0f32).unwrap();
+    src.poll();
+    dst.poll();
+    assert!(input.get_status().was_set_remote());
+    assert_eq!(input.get_value_scalar::<f32>().unwrap().0, 8.0);
+  }
+
+  #[test]
+  fn filters_lists_by_name() {
+    let device = Device::create("fake_test_filter");
+    let _a = device.create_signal::<f32>("a", mpr_dir::MPR_DIR_OUT);
+    let _b = device.create_signal::<i32>("b", mpr_dir::MPR_DIR_IN);
+    let _c = device.create_signal::<f64>("c", mpr_dir::MPR_DIR_IN);
+
+    assert_eq!(filter(&device, c"b", mpr_op::MPR_OP_EQ), vec!["b"]);
+    assert_eq!(filter(&device, c"b", mpr_op::MPR_OP_NEQ), vec!["a", "c"]);
+    assert!(filter(&device, c"missing", mpr_op::MPR_OP_EQ).is_empty());
+    assert_eq!(device.signal("c").map(|s| s.get_vector_length()), Some(1));
+  }
+
+  #[test]
+  #[should_panic(expected = "fake backend: unsupported list filter")]
+  fn unsupported_filters_panic() {
+    let device = Device::create("fake_test_unsupported_filter");
+    let _a = device.create_signal::<f32>("a", mpr_dir::MPR_DIR_OUT);
+    filter(&device, c"a", mpr_op::MPR_OP_GT);
+  }
+
+  #[test]
+  fn invalid_signal_type_is_read_as_float() {
+    let device = Device::create("fake_test_invalid_type");
+    let mut signal = device.create_signal::<f32>("value", mpr_dir::MPR_DIR_OUT);
+    signal.set_property(mpr_prop::MPR_PROP_TYPE, 1234);
+    signal.set_value_scalar(&2.5f32).unwrap();
+    assert_eq!(signal.get_value_scalar::<f32>().unwrap().0, 2.5);
+  }
+
+  #[test]
+  fn properties_round_trip() {
+    let device = Device::create("fake_test_props");
+    let signal = device.create_signal::<f32>("prop", mpr_dir::MPR_DIR_OUT);
+
+    signal.set_property(mpr_prop::MPR_PROP_MIN, -1.5f32);
+    assert_eq!(signal.get_property::<f32>(mpr_prop::MPR_PROP_MIN), Ok(-1.5));
+    signal.set_property_vec(mpr_prop::MPR_PROP_MAX, &[1, 2, 3]);
+    assert_eq!(signal.get_property_vec::<i32>(mpr_prop::MPR_PROP_MAX), Ok(vec![1, 2, 3]));
+    signal.set_property_str(mpr_prop::MPR_PROP_UNIT, "Hz");
+    assert_eq!(signal.get_property_str(mpr_prop::MPR_PROP_UNIT).as_deref(), Ok("Hz"));
+    // reading a property as the wrong type fails instead of reinterpreting it
+    assert!(signal.get_property::<f64>(mpr_prop::MPR_PROP_MIN).is_err());
+  }
+}
@@ -0,0 +1,215 @@
+//! # mapper-cli
+//! A command line tool for inspecting and editing the libmapper graph from scripts.
+//! Build with `cargo build --features cli`, then run `mapper-cli help` for a list of commands.
+//!
+//! Pass `--json` to `ls`, `props` and `watch` to get machine readable output.
+
+use std::{env, error::Error, process, time::Duration};
+
+use libmapper_rs::{constants::{mpr_loc, mpr_prop, mpr_type}, graph::{Graph, Map}, monitor::Monitor, object::MapperObject, session::Session, signal::Signal};
+
+const USAGE: &str = "usage: mapper-cli <command> [args] [--json]
+
+commands:
+  ls                                 list every device, signal and map
+  map <src> <dst> [--expr <expr>]    map two signals, e.g. `map synth.1/freq filter.1/cutoff`
+  unmap <src> <dst>                  remove the map between two signals
+  props <object>                     print the properties of a device (`synth.1`) or signal (`synth.1/freq`)
+  set-prop <object> <key> <value>    set a custom property, numbers are stored as int or double
+  watch <signal>                     print every value sent by a signal
+  save <file> [devices...]           save the maps between devices to a session file
+  load <file>                        restore the maps in a session file";
+
+/// How long to listen for devices before running a command.
+const DISCOVERY_TIME: Duration = Duration::from_secs(1);
+/// How long to wait for maps to be created or restored.
+const MAP_TIMEOUT: Duration = Duration::from_secs(5);
+
+type CliResult = Result<(), Box<dyn Error>>;
+
+fn main() {
+  let mut args: Vec<String> = env::args().skip(1).collect();
+  let json = take_flag(&mut args, "--json");
+  let expr = take_option(&mut args, "--expr");
+
+  let Some(command) = args.first().cloned() else {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+  };
+  let args = &args[1..];
+  if command == "help" {
+    println!("{}", USAGE);
+    return;
+  }
+
+  let graph = Graph::create();
+  graph.subscribe(None, &[mpr_type::MPR_DEV, mpr_type::MPR_SIG, mpr_type::MPR_MAP]);
+  graph.poll_and_block(DISCOVERY_TIME);
+
+  let result = match (command.as_str(), args) {
+    ("ls", []) => ls(&graph, json),
+    ("map", [src, dst]) => map(&graph, src, dst, expr.as_deref()),
+    ("unmap", [src, dst]) => unmap(&graph, src, dst),
+    ("props", [object]) => props(&graph, object, json),
+    ("set-prop", [object, key, value]) => set_prop(&graph, object, key, value),
+    ("watch", [signal]) => watch(&graph, signal, json),
+    ("save", [file, devices @ ..]) => save(&graph, file, devices),
+    ("load", [file]) => load(&graph, file),
+    _ => Err(USAGE.into())
+  };
+  if let Err(e) = result {
+    eprintln!("error: {}", e);
+    process::exit(1);
+  }
+}
+
+/// Remove `flag` from the arguments, returning `true` if it was present.
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+  let len = args.len();
+  args.retain(|arg| arg != flag);
+  args.len() != len
+}
+
+/// Remove `option` and the value following it from the arguments.
+fn take_option(args: &mut Vec<String>, option: &str) -> Option<String> {
+  let index = args.iter().position(|arg| arg == option)?;
+  args.remove(index);
+  (index < args.len()).then(|| args.remove(index))
+}
+
+fn find_signal(graph: &Graph, path: &str) -> Result<Signal, Box<dyn Error>> {
+  graph.find_signal(path).ok_or_else(|| format!("signal '{}' not found", path).into())
+}
+
+/// Get the full `device/signal` path of a signal.
+fn signal_path(signal: &Signal) -> String {
+  format!("{}/{}", signal.get_device_name().unwrap_or_default(),
+    signal.get_property_str(mpr_prop::MPR_PROP_NAME).unwrap_or_default())
+}
+
+fn ls(graph: &Graph, json: bool) -> CliResult {
+  let snapshot = graph.snapshot();
+  if json {
+    println!("{}", serde_json::to_string_pretty(&snapshot)?);
+    return Ok(());
+  }
+  for device in &snapshot.devices {
+    println!("{}", device.name);
+    for signal in &device.signals {
+      println!("  {} ({:?}, {}[{}])", signal.name, signal.direction, signal.data_type, signal.vector_length);
+    }
+  }
+  for map in &snapshot.maps {
+    let muted = if map.muted { " (muted)" } else { "" };
+    println!("{} -> {}: {}{}", map.sources.join(", "), map.destinations.join(", "),
+      map.expression.as_deref().unwrap_or(""), muted);
+  }
+  Ok(())
+}
+
+fn map(graph: &Graph, src: &str, dst: &str, expr: Option<&str>) -> CliResult {
+  let map = Map::try_create(&find_signal(graph, src)?, &find_signal(graph, dst)?)?;
+  if let Some(expr) = expr {
+    map.try_set_expr(expr)?;
+  }
+  map.push_and_wait(MAP_TIMEOUT)?;
+  println!("{}", map.id());
+  Ok(())
+}
+
+fn unmap(graph: &Graph, src: &str, dst: &str) -> CliResult {
+  let matches = |map: &Map, endpoint, path: &str| {
+    map.get_signals(endpoint).iter().any(|sig| signal_path(sig) == path.trim_start_matches('/'))
+  };
+  let map = graph.get_maps().into_iter()
+    .find(|map| matches(map, mpr_loc::MPR_LOC_SRC, src) && matches(map, mpr_loc::MPR_LOC_DST, dst))
+    .ok_or_else(|| format!("no map from '{}' to '{}'", src, dst))?;
+  map.unmap();
+  graph.poll_and_block(Duration::from_millis(100));
+  Ok(())
+}
+
+fn props(graph: &Graph, object: &str, json: bool) -> CliResult {
+  fn print(object: &impl MapperObject, json: bool) -> CliResult {
+    let properties = object.get_properties();
+    if json {
+      let properties: std::collections::BTreeMap<_, _> = properties.into_iter().collect();
+      println!("{}", serde_json::to_string_pretty(&properties)?);
+    } else {
+      for (key, value) in properties {
+        println!("{}: {:?}", key, value);
+      }
+    }
+    Ok(())
+  }
+
+  if object.trim_start_matches('/').contains('/') {
+    print(&find_signal(graph, object)?, json)
+  } else {
+    let device = graph.find_device(object).ok_or_else(|| format!("device '{}' not found", object))?;
+    print(&device, json)
+  }
+}
+
+fn set_prop(graph: &Graph, object: &str, key: &str, value: &str) -> CliResult {
+  fn set(object: &impl MapperObject, key: &str, value: &str) -> CliResult {
+    if let Ok(value) = value.parse::<i32>() {
+      object.try_set_custom_property(key, value, true)?;
+    } else if let Ok(value) = value.parse::<f64>() {
+      object.try_set_custom_property(key, value, true)?;
+    } else {
+      object.try_set_custom_property_str(key, value, true)?;
+    }
+    object.push();
+    Ok(())
+  }
+
+  if object.trim_start_matches('/').contains('/') {
+    set(&find_signal(graph, object)?, key, value)?;
+  } else {
+    let device = graph.find_device(object).ok_or_else(|| format!("device '{}' not found", object))?;
+    set(&device, key, value)?;
+  }
+  graph.poll_and_block(Duration::from_millis(100));
+  Ok(())
+}
+
+fn watch(graph: &Graph, path: &str, json: bool) -> CliResult {
+  let mut monitor = Monitor::create(graph, path)?;
+  loop {
+    if !monitor.poll(Duration::from_millis(10)) {
+      continue;
+    }
+    let (value, time) = match monitor.signal().get_data_type() {
+      mpr_type::MPR_FLT => monitor.value::<f32>().map(|(v, t)| (serde_json::json!(v), t)),
+      mpr_type::MPR_DBL => monitor.value::<f64>().map(|(v, t)| (serde_json::json!(v), t)),
+      mpr_type::MPR_INT32 => monitor.value::<i32>().map(|(v, t)| (serde_json::json!(v), t)),
+      other => return Err(format!("unsupported signal type {:?}", other).into())
+    }.map_err(libmapper_rs::Error::from)?;
+    if json {
+      println!("{}", serde_json::json!({ "time": time.as_secs_f64(), "value": value }));
+    } else {
+      println!("{} {}", time, value);
+    }
+  }
+}
+
+fn save(graph: &Graph, file: &str, devices: &[String]) -> CliResult {
+  let devices: Vec<&str> = devices.iter().map(String::as_str).collect();
+  let session = Session::capture(graph, &devices);
+  session.save(file)?;
+  println!("saved {} maps", session.maps.len());
+  Ok(())
+}
+
+fn load(graph: &Graph, file: &str) -> CliResult {
+  let session = Session::load(file)?;
+  let maps = session.restore(graph, MAP_TIMEOUT);
+  // give the graph a chance to announce the new maps
+  graph.poll_and_block(Duration::from_millis(100));
+  println!("restored {} of {} maps", maps.len(), session.maps.len());
+  if maps.len() != session.maps.len() {
+    return Err("some maps could not be restored".into());
+  }
+  Ok(())
+}
@@ -1,11 +1,15 @@
 use std::ffi::CString;
 use std::os::raw::c_int;
 use std::ptr;
-use std::time::Duration;
-use crate::bindings::{mpr_dev, mpr_dev_free, mpr_dev_get_is_ready, mpr_dev_get_sigs, mpr_dev_new, mpr_dev_poll, mpr_dir, mpr_obj, mpr_prop, mpr_sig_new, mpr_type};
-use crate::graph::Graph;
+use std::time::{Duration, Instant};
+use crate::backend::{mpr_dev, mpr_dev_free, mpr_dev_get_is_ready, mpr_dev_get_maps, mpr_dev_get_sigs, mpr_dev_get_time, mpr_dev_set_time, mpr_dev_update_maps, mpr_dev_new, mpr_dev_poll, mpr_dir, mpr_prop, mpr_sig_new, mpr_type};
+use crate::error::Error;
+use crate::graph::{Graph, Map};
 use crate::object::MapperObject;
-use crate::signal::Signal;
+use crate::signal::{Signal, SignalError};
+use crate::time::Timetag;
+use crate::trace;
+use crate::version::check_compatibility;
 
 /// A device is libmapper's connection to the distributed graph.
 /// Each device is a collection of signal instances and their metadata.
@@ -52,27 +56,53 @@ impl Device<'_> {
     /// 
     /// # Notes
     /// If you plan on creating multiple devices, consider using (Device::create_from_graph)[Device::create_from_graph] instead to pool resources.
+    /// 
+    /// # Panics
+    /// Panics if the name contains a NUL byte or libmapper fails to create the device. Use [try_create](Device::try_create) to handle these cases.
     pub fn create(name: &str) -> Device {
-        let name_ptr = CString::new(name).expect("CString::new failed");
-        unsafe {
-            Device {
-                owned: true,
-                handle: mpr_dev_new(name_ptr.as_ptr(), ptr::null_mut()),
-                graph: None
-            }
+        Device::try_create(name).expect("failed to create device")
+    }
+    /// Create a new device with the given name, returning an error if the name is invalid or libmapper fails to create the device.
+    /// 
+    /// Returns [`Error::Version`](Error::Version) if the loaded libmapper is not supported by this crate (see [check_compatibility](crate::version::check_compatibility)).
+    pub fn try_create(name: &str) -> Result<Device<'static>, Error> {
+        check_compatibility()?;
+        let name_ptr = CString::new(name)?;
+        let handle = unsafe { mpr_dev_new(name_ptr.as_ptr(), ptr::null_mut()) };
+        if handle.is_null() {
+            trace::event!(warn, name, "mpr_dev_new failed");
+            return Err(Error::CreationFailed);
         }
+        trace::event!(debug, name, "created device");
+        Ok(Device {
+            owned: true,
+            handle,
+            graph: None
+        })
     }
     /// Create a new device with a shared graph.
     /// Sharing a graph between devices allows them to pool some resources and networking, potentially improving performance.
+    /// 
+    /// # Panics
+    /// Panics if the name contains a NUL byte or libmapper fails to create the device. Use [try_create_from_graph](Device::try_create_from_graph) to handle these cases.
     pub fn create_from_graph<'a>(name: &str, graph: &'a Graph) -> Device<'a> {
-        let name_ptr = CString::new(name).expect("CString::new failed");
-        unsafe {
-            Device {
-                owned: true,
-                handle: mpr_dev_new(name_ptr.as_ptr(), graph.handle),
-                graph: Some(graph)
-            }
+        Device::try_create_from_graph(name, graph).expect("failed to create device")
+    }
+    /// Create a new device with a shared graph, returning an error if the name is invalid or libmapper fails to create the device.
+    pub fn try_create_from_graph<'a>(name: &str, graph: &'a Graph) -> Result<Device<'a>, Error> {
+        check_compatibility()?;
+        let name_ptr = CString::new(name)?;
+        let handle = unsafe { mpr_dev_new(name_ptr.as_ptr(), graph.handle) };
+        if handle.is_null() {
+            trace::event!(warn, name, "mpr_dev_new failed");
+            return Err(Error::CreationFailed);
         }
+        trace::event!(debug, name, shared_graph = true, "created device");
+        Ok(Device {
+            owned: true,
+            handle,
+            graph: Some(graph)
+        })
     }
 }
 
@@ -113,6 +143,208 @@ impl Device<'_> {
             mpr_dev_get_is_ready(self.handle) > 0
         }
     }
+    /// Poll the device until it is ready, or until `timeout` has elapsed.
+    /// 
+    /// Returns [`Error::Timeout`](Error::Timeout) if the device did not become ready in time (e.g. if the network is unavailable).
+    pub fn wait_until_ready(&self, timeout: Duration) -> Result<(), Error> {
+        let _span = trace::span!(DEBUG, "wait_until_ready", device = ?self.get_property_str(mpr_prop::MPR_PROP_NAME).ok());
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.is_ready() {
+                trace::event!(debug, name = ?self.get_property_str(mpr_prop::MPR_PROP_NAME).ok(), "device ready");
+                return Ok(());
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                trace::event!(warn, ?timeout, "device did not become ready");
+                return Err(Error::Timeout);
+            }
+            self.poll_and_block(remaining.min(WAIT_POLL_INTERVAL));
+        }
+    }
+}
+
+/// How long to block for between readiness checks when waiting for an object to become ready.
+pub(crate) const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Split `time` between polling `devices` devices, blocking for at least 1ms each.
+///
+/// libmapper polls with millisecond precision and returns immediately for a 0ms poll, so without the minimum
+/// a waiting loop close to its deadline, or sharing its time between many devices, would busy-spin.
+pub(crate) fn poll_share(time: Duration, devices: usize) -> Duration {
+    (time / devices.max(1) as u32).max(Duration::from_millis(1))
+}
+
+/// A builder for creating a [Device] with initial properties.
+/// 
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use libmapper_rs::device::DeviceBuilder;
+/// use libmapper_rs::constants::mpr_prop;
+/// let dev = DeviceBuilder::new("synth")
+///     .property_str(mpr_prop::MPR_PROP_HOST, "studio-a")
+///     .custom_property("voices", 8, true)
+///     .build_and_wait(Duration::from_secs(5))
+///     .expect("device did not become ready");
+/// ```
+pub struct DeviceBuilder<'a> {
+    name: String,
+    graph: Option<&'a Graph>,
+    properties: Vec<PropertySetter<'a>>
+}
+
+/// A deferred property write, applied to a device once it has been created.
+type PropertySetter<'a> = Box<dyn Fn(&Device) -> Result<(), Error> + 'a>;
+
+impl<'a> DeviceBuilder<'a> {
+    /// Start building a device with the given name.
+    pub fn new(name: &str) -> DeviceBuilder<'a> {
+        DeviceBuilder {
+            name: name.to_string(),
+            graph: None,
+            properties: Vec::new()
+        }
+    }
+    /// Create the device with a shared graph (see [Device::create_from_graph]).
+    pub fn graph(mut self, graph: &'a Graph) -> Self {
+        self.graph = Some(graph);
+        self
+    }
+    /// Set a property on the device to a numerical value once it is created.
+    pub fn property<T: MappableType + Copy + 'a>(mut self, property: mpr_prop, value: T) -> Self {
+        self.properties.push(Box::new(move |dev| {
+            dev.set_property(property, value);
+            Ok(())
+        }));
+        self
+    }
+    /// Set a property on the device to a string value once it is created.
+    pub fn property_str(mut self, property: mpr_prop, value: &str) -> Self {
+        let value = value.to_string();
+        self.properties.push(Box::new(move |dev| dev.try_set_property_str(property, &value)));
+        self
+    }
+    /// Set a user-defined property on the device once it is created.
+    pub fn custom_property<T: MappableType + Copy + 'a>(mut self, key: &str, value: T, publish: bool) -> Self {
+        let key = key.to_string();
+        self.properties.push(Box::new(move |dev| dev.try_set_custom_property(&key, value, publish)));
+        self
+    }
+    /// Set a user-defined string property on the device once it is created.
+    pub fn custom_property_str(mut self, key: &str, value: &str, publish: bool) -> Self {
+        let key = key.to_string();
+        let value = value.to_string();
+        self.properties.push(Box::new(move |dev| dev.try_set_custom_property_str(&key, &value, publish)));
+        self
+    }
+    /// Create the device and apply it's initial properties, without waiting for it to become ready.
+    pub fn build(self) -> Result<Device<'a>, Error> {
+        let dev = match self.graph {
+            Some(graph) => Device::try_create_from_graph(&self.name, graph)?,
+            None => Device::try_create(&self.name)?
+        };
+        for property in &self.properties {
+            property(&dev)?;
+        }
+        Ok(dev)
+    }
+    /// Create the device and poll it until it is ready.
+    /// 
+    /// Returns [`Error::Timeout`](Error::Timeout) if the device did not become ready within `timeout`.
+    pub fn build_and_wait(self, timeout: Duration) -> Result<Device<'a>, Error> {
+        let dev = self.build()?;
+        dev.wait_until_ready(timeout)?;
+        Ok(dev)
+    }
+}
+
+impl Device<'_> {
+    /// Get the device's current time.
+    /// This is the timetag that will be attached to outgoing signal updates.
+    pub fn time(&self) -> Timetag {
+        Timetag(unsafe {
+            mpr_dev_get_time(self.handle)
+        })
+    }
+    /// Set the device's current time.
+    /// Signal updates made after calling this function will be sent with the passed timetag,
+    /// until the device is next polled and the time is reset to the system clock.
+    /// 
+    /// # Examples
+    /// Replay a value with the timetag it was originally recorded with:
+    /// ```
+    /// use libmapper_rs::device::Device;
+    /// use libmapper_rs::signal::Signal;
+    /// use libmapper_rs::time::Timetag;
+    /// fn replay(dev: &Device, sig: &mut Signal, value: f32, time: Timetag) {
+    ///     dev.set_time(time);
+    ///     sig.set_value_scalar(&value).unwrap();
+    ///     dev.poll();
+    /// }
+    /// ```
+    pub fn set_time(&self, time: Timetag) {
+        unsafe {
+            mpr_dev_set_time(self.handle, time.0);
+        }
+    }
+    /// Send any pending signal updates to the network immediately instead of waiting for the next poll.
+    pub fn update_maps(&self) {
+        unsafe {
+            mpr_dev_update_maps(self.handle);
+        }
+    }
+    /// Update multiple signals as a single, coherent bundle.
+    /// 
+    /// All values set through the passed [Batch] share one timetag, and are flushed together with a single call to
+    /// [update_maps](Device::update_maps) once the closure returns.
+    /// 
+    /// # Examples
+    /// ```
+    /// use libmapper_rs::device::Device;
+    /// use libmapper_rs::signal::Signal;
+    /// fn send_frame(dev: &Device, x: &mut Signal, y: &mut Signal) {
+    ///     dev.batch(|b| {
+    ///         b.set(x, &[0.5f32]).unwrap();
+    ///         b.set(y, &[0.25f32]).unwrap();
+    ///     });
+    /// }
+    /// ```
+    pub fn batch<R, F: FnOnce(&mut Batch) -> R>(&self, f: F) -> R {
+        let mut batch = Batch {
+            device: self.handle,
+            time: Timetag::now()
+        };
+        self.set_time(batch.time);
+        let result = f(&mut batch);
+        self.update_maps();
+        result
+    }
+}
+
+/// A set of signal updates that will be sent together.
+/// Created by [Device::batch](Device::batch).
+pub struct Batch {
+    device: mpr_dev,
+    time: Timetag
+}
+
+impl Batch {
+    /// Get the timetag shared by every update in this batch.
+    pub fn time(&self) -> Timetag {
+        self.time
+    }
+    /// Set the value of a signal as part of this batch.
+    /// The signal should belong to the device that created the batch, otherwise it will not be part of the bundle.
+    /// 
+    /// See [Signal::set_value](Signal::set_value) for the possible errors.
+    pub fn set<T: MappableType + Copy>(&mut self, signal: &mut Signal, values: &[T]) -> Result<(), SignalError> {
+        // keep the shared time in case another update on the device moved it
+        unsafe {
+            mpr_dev_set_time(self.device, self.time.0);
+        }
+        signal.set_value(values)
+    }
 }
 
 /// Marker trait for types that are bit-compatible with the libmapper C library.
@@ -153,6 +385,67 @@ impl MappableType for i64 {
     }
 }
 
+/// A complete signal value whose vector length is known at compile time.
+/// 
+/// This is implemented for the numeric types libmapper signals can hold (`i32`, `f32` and `f64`) as signals of length 1,
+/// and for non-empty fixed-length arrays of them. For example, `[f32; 3]` describes a signal holding 3 `f32` values.
+/// 
+/// This trait is sealed: values are read and written as `LENGTH` contiguous elements, so it can't be implemented outside this crate.
+/// 
+/// Empty arrays are rejected at compile time:
+/// ```compile_fail
+/// use libmapper_rs::device::SignalType;
+/// const LENGTH: u32 = <[f32; 0] as SignalType>::LENGTH;
+/// ```
+/// So are types that aren't numeric, such as [Timetag](crate::time::Timetag):
+/// ```compile_fail
+/// use libmapper_rs::{device::SignalType, time::Timetag};
+/// const LENGTH: u32 = <Timetag as SignalType>::LENGTH;
+/// ```
+pub trait SignalType: Copy + sealed::Sealed {
+    /// The type of each element of the signal's vector.
+    type Element: MappableType + Copy;
+    /// The vector length of the signal.
+    const LENGTH: u32;
+
+    /// View this value as a slice of it's elements.
+    fn as_elements(&self) -> &[Self::Element] {
+        unsafe {
+            std::slice::from_raw_parts(self as *const Self as *const Self::Element, Self::LENGTH as usize)
+        }
+    }
+}
+
+impl<T: sealed::Numeric> SignalType for T {
+    type Element = T;
+    const LENGTH: u32 = 1;
+}
+
+impl<T: sealed::Numeric, const N: usize> SignalType for [T; N] {
+    type Element = T;
+    const LENGTH: u32 = {
+        assert!(N > 0, "signals must have at least one element");
+        N as u32
+    };
+}
+
+mod sealed {
+    use super::MappableType;
+
+    /// Restricts [SignalType](super::SignalType) to the types whose layout is `LENGTH` contiguous elements.
+    pub trait Sealed {}
+
+    /// The element types libmapper accepts for signals (`mpr_type_get_is_num`).
+    pub trait Numeric: MappableType + Copy {}
+
+    impl Numeric for i32 {}
+    impl Numeric for f32 {}
+    impl Numeric for f64 {}
+
+    impl<T: Numeric> Sealed for T {}
+    impl<T: Numeric, const N: usize> Sealed for [T; N] {}
+}
+
 impl<'a> Device<'a> {
     /// Get the shared graph used by this device.
     /// If the device was created with [Device::create](Device::create) this will return None.
@@ -169,8 +462,9 @@ impl Device<'_> {
     /// Create a signal with the given name and direction.
     /// 
     /// # Notes
-    /// - The signal will have a vector length of 1 (i.e. single value).
     /// - The passed generic parameter controls what type of data the signal will hold.
+    /// - If the generic parameter is a single value the signal will have a vector length of 1,
+    ///   if it is an array (e.g. `[f32; 3]`) the vector length will be the length of the array.
     /// 
     /// # Examples
     /// ```
@@ -179,42 +473,92 @@ impl Device<'_> {
     /// fn setup_signals(dev: &Device) {
     ///     // create an outgoing signal that outputs a single f64 value
     ///     let sig = dev.create_signal::<f64>("test_signal", mpr_dir::MPR_DIR_OUT);
+    ///     // create an outgoing signal that outputs 3 f32 values
+    ///     let position = dev.create_signal::<[f32; 3]>("position", mpr_dir::MPR_DIR_OUT);
     /// }
     /// ```
-    pub fn create_signal<T: MappableType + Copy>(&self, name: &str, direction: mpr_dir) -> Signal {
-        self.create_vector_signal::<T>(name, direction, 1)
+    /// 
+    /// # Panics
+    /// Panics if the name contains a NUL byte or libmapper fails to create the signal. Use [try_create_signal](Device::try_create_signal) to handle these cases.
+    pub fn create_signal<T: SignalType>(&self, name: &str, direction: mpr_dir) -> Signal {
+        self.try_create_signal::<T>(name, direction).expect("failed to create signal")
+    }
+    /// Create a signal with the given name and direction, returning an error if the name is invalid or libmapper fails to create the signal.
+    pub fn try_create_signal<T: SignalType>(&self, name: &str, direction: mpr_dir) -> Result<Signal, Error> {
+        self.create_signal_of_type(name, direction, T::Element::get_mpr_type(), T::LENGTH)
     }
     /// Create a signal with the given name, direction, and vector length.
     /// 
     /// # Notes
     /// - The passed generic parameter controls what type of data the signal will hold.
     /// 
+    /// # Panics
+    /// Panics if the name contains a NUL byte or libmapper fails to create the signal. Use [try_create_vector_signal](Device::try_create_vector_signal) to handle these cases.
     pub fn create_vector_signal<T: MappableType + Copy>(&self, name: &str, direction: mpr_dir, vector_length: u32) -> Signal {
-        let data_type: mpr_type = T::get_mpr_type();
-
-        let name_ptr = CString::new(name).expect("CString::new failed");
-        unsafe {
-            Signal {
-                handle: mpr_sig_new(self.handle, direction, name_ptr.as_ptr(), vector_length as i32, 
-                    data_type, ptr::null(), ptr::null(), ptr::null(), ptr::null_mut(), None, 0),
-                data_type,
-                owned: true,
-                vector_length
-            }
+        self.try_create_vector_signal::<T>(name, direction, vector_length).expect("failed to create signal")
+    }
+    /// Create a signal with the given name, direction, and vector length,
+    /// returning an error if the name is invalid or libmapper fails to create the signal.
+    pub fn try_create_vector_signal<T: MappableType + Copy>(&self, name: &str, direction: mpr_dir, vector_length: u32) -> Result<Signal, Error> {
+        self.create_signal_of_type(name, direction, T::get_mpr_type(), vector_length)
+    }
+    /// Create a signal whose data type is only known at runtime (e.g. to match a remote signal).
+    pub(crate) fn create_signal_of_type(&self, name: &str, direction: mpr_dir, data_type: mpr_type, vector_length: u32) -> Result<Signal, Error> {
+        let name_ptr = CString::new(name)?;
+        let handle = unsafe {
+            mpr_sig_new(self.handle, direction, name_ptr.as_ptr(), vector_length as i32, 
+                data_type, ptr::null(), ptr::null(), ptr::null(), ptr::null_mut(), None, 0)
+        };
+        if handle.is_null() {
+            return Err(Error::CreationFailed);
         }
+        Ok(Signal {
+            handle,
+            data_type,
+            owned: true,
+            vector_length,
+            overflow_handler: None
+        })
     }
     /// Get a list of all signals owned by this device.
+    /// 
+    /// Signals whose type or length can't be read are skipped, use [try_get_signals](Device::try_get_signals) to detect this.
     pub fn get_signals(&self, direction: mpr_dir) -> Vec<Signal> {
         let list = unsafe {mpr_dev_get_sigs(self.handle, direction)};
-        crate::util::read_list(list, |ptr| {
-            let data_type = (ptr as mpr_obj).get_property::<mpr_type>(mpr_prop::MPR_PROP_TYPE).unwrap();
-            let vector_length = (ptr as mpr_obj).get_property::<i32>(mpr_prop::MPR_PROP_LEN).unwrap() as u32;
-            Signal {
-                handle: ptr,
-                data_type,
-                owned: false,
-                vector_length 
-            }
+        crate::util::read_list(list, Signal::try_from_unowned).into_iter().filter_map(Result::ok).collect()
+    }
+    /// Get a list of all signals owned by this device.
+    /// Returns an error if the type or length of any signal can't be read.
+    pub fn try_get_signals(&self, direction: mpr_dir) -> Result<Vec<Signal>, Error> {
+        let list = unsafe {mpr_dev_get_sigs(self.handle, direction)};
+        let signals = crate::util::read_list(list, Signal::try_from_unowned).into_iter().collect::<Result<Vec<_>, _>>()?;
+        Ok(signals)
+    }
+    /// Get a list of all maps connected to this device's signals.
+    /// 
+    /// `direction` selects incoming (`MPR_DIR_IN`), outgoing (`MPR_DIR_OUT`) or all (`MPR_DIR_ANY`) maps.
+    pub fn get_maps(&self, direction: mpr_dir) -> Vec<Map> {
+        let list = unsafe {mpr_dev_get_maps(self.handle, direction)};
+        crate::util::read_list(list, |ptr| Map {
+            handle: ptr,
+            owned: false
         })
     }
+    /// Find a signal owned by this device by it's name.
+    /// 
+    /// Returns `None` if this device has no signal with the given name.
+    /// 
+    /// # Examples
+    /// ```
+    /// use libmapper_rs::device::Device;
+    /// fn read_slider(dev: &Device) -> Option<f32> {
+    ///     let sig = dev.signal("slider")?;
+    ///     sig.get_value_scalar::<f32>().ok().map(|(v, _)| v)
+    /// }
+    /// ```
+    pub fn signal(&self, name: &str) -> Option<Signal> {
+        let list = unsafe {mpr_dev_get_sigs(self.handle, mpr_dir::MPR_DIR_ANY)};
+        let list = crate::util::filter_by_name(list, name)?;
+        crate::util::first_in_list(list).and_then(|ptr| Signal::try_from_unowned(ptr).ok())
+    }
 }
\ No newline at end of file
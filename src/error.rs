@@ -0,0 +1,117 @@
+//! The crate-wide [Error] type returned by fallible (`try_*`) functions.
+use std::{ffi::NulError, fmt, str::Utf8Error};
+
+use crate::{monitor::MonitorError, object::PropertyError, record::RecordError, signal::SignalError, version::VersionError};
+#[cfg(feature = "osc-bridge")]
+use crate::osc_bridge::OscError;
+#[cfg(feature = "session")]
+use crate::session::SessionError;
+
+/// Any error that can occur when working with libmapper.
+///
+/// Module specific errors (e.g. [PropertyError] or [SignalError]) can be converted into this type with `?`.
+#[derive(Debug, PartialEq)]
+pub enum Error {
+  /// A string passed to libmapper contained an interior NUL byte.
+  NulInString,
+  /// A string returned by libmapper was not valid UTF-8.
+  InvalidUtf8,
+  /// libmapper failed to create an object and returned a null handle.
+  CreationFailed,
+  /// An object did not become ready before the timeout elapsed.
+  Timeout,
+  /// An error occurred while reading or writing a property.
+  Property(PropertyError),
+  /// An error occurred while reading or writing a signal value.
+  Signal(SignalError),
+  /// An error occurred while creating a [Monitor](crate::monitor::Monitor).
+  Monitor(MonitorError),
+  /// An error occurred while reading or writing a [Recording](crate::record::Recording).
+  Record(RecordError),
+  /// The loaded libmapper library is not compatible with this crate.
+  Version(VersionError),
+  /// An error occurred while reading or writing a [Session](crate::session::Session) file.
+  #[cfg(feature = "session")]
+  Session(SessionError),
+  /// An error occurred while running an [OscBridge](crate::osc_bridge::OscBridge).
+  #[cfg(feature = "osc-bridge")]
+  Osc(OscError)
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::NulInString => write!(f, "string contains an interior NUL byte"),
+      Error::InvalidUtf8 => write!(f, "string returned by libmapper is not valid UTF-8"),
+      Error::CreationFailed => write!(f, "libmapper failed to create the object"),
+      Error::Timeout => write!(f, "timed out waiting for the object to become ready"),
+      Error::Property(e) => write!(f, "property error: {}", e),
+      Error::Signal(e) => write!(f, "signal error: {}", e),
+      Error::Monitor(e) => write!(f, "monitor error: {}", e),
+      Error::Record(e) => write!(f, "record error: {}", e),
+      Error::Version(e) => write!(f, "version error: {}", e),
+      #[cfg(feature = "session")]
+      Error::Session(e) => write!(f, "session error: {}", e),
+      #[cfg(feature = "osc-bridge")]
+      Error::Osc(e) => write!(f, "OSC error: {}", e)
+    }
+  }
+}
+
+impl std::error::Error for Error {}
+
+impl From<NulError> for Error {
+  fn from(_: NulError) -> Self {
+    Error::NulInString
+  }
+}
+
+impl From<Utf8Error> for Error {
+  fn from(_: Utf8Error) -> Self {
+    Error::InvalidUtf8
+  }
+}
+
+impl From<PropertyError> for Error {
+  fn from(e: PropertyError) -> Self {
+    Error::Property(e)
+  }
+}
+
+impl From<SignalError> for Error {
+  fn from(e: SignalError) -> Self {
+    Error::Signal(e)
+  }
+}
+
+impl From<MonitorError> for Error {
+  fn from(e: MonitorError) -> Self {
+    Error::Monitor(e)
+  }
+}
+
+impl From<RecordError> for Error {
+  fn from(e: RecordError) -> Self {
+    Error::Record(e)
+  }
+}
+
+impl From<VersionError> for Error {
+  fn from(e: VersionError) -> Self {
+    Error::Version(e)
+  }
+}
+
+#[cfg(feature = "session")]
+impl From<SessionError> for Error {
+  fn from(e: SessionError) -> Self {
+    Error::Session(e)
+  }
+}
+
+#[cfg(feature = "osc-bridge")]
+impl From<OscError> for Error {
+  fn from(e: OscError) -> Self {
+    Error::Osc(e)
+  }
+}
@@ -3,9 +3,9 @@
 //! The [Map] type is used to create a connection between two [Signal] instances.
 //! 
 //! The [Graph] type can be shared between devices to improve performance and memory usage.
-use std::{ffi::c_int, ptr, time::Duration};
+use std::{ffi::{c_int, c_void}, ptr, time::{Duration, Instant}};
 
-use crate::{bindings::*, device::Device, object::MapperObject, signal::Signal, util::read_list};
+use crate::{backend::*, device::{poll_share, Device, WAIT_POLL_INTERVAL}, error::Error, object::MapperObject, signal::Signal, trace, util::read_list, version::check_compatibility};
 
 /// A graph is a lightweight connection to libmapper's distributed graph.
 /// You can use a graph to create maps and query the state of the graph.
@@ -18,11 +18,29 @@ unsafe impl Send for Graph {}
 unsafe impl Sync for Graph {}
 
 impl Graph {
+  /// Create a new graph.
+  /// 
+  /// # Panics
+  /// Panics if libmapper fails to create the graph. Use [try_create](Graph::try_create) to handle this case.
   pub fn create() -> Graph {
-    Graph {
-      owned: true,
-      handle: unsafe { mpr_graph_new(0) }
+    Graph::try_create().expect("mpr_graph_new failed")
+  }
+
+  /// Create a new graph, returning an error if libmapper fails to create it.
+  /// 
+  /// Returns [`Error::Version`](Error::Version) if the loaded libmapper is not supported by this crate (see [check_compatibility](crate::version::check_compatibility)).
+  pub fn try_create() -> Result<Graph, Error> {
+    check_compatibility()?;
+    let handle = unsafe { mpr_graph_new(0) };
+    if handle.is_null() {
+      trace::event!(warn, "mpr_graph_new failed");
+      return Err(Error::CreationFailed);
     }
+    trace::event!(debug, "created graph");
+    Ok(Graph {
+      owned: true,
+      handle
+    })
   }
 }
 
@@ -59,6 +77,7 @@ impl Graph {
   /// 
   /// This function must be called before functions like [get_devices](Graph::get_devices) will return any results.
   pub fn subscribe(&self, device: Option<Device>, types: &[mpr_type]) {
+    trace::event!(debug, ?types, device = ?device.as_ref().and_then(|d| d.get_property_str(mpr_prop::MPR_PROP_NAME).ok()), "subscribed graph");
     unsafe {
       let types_bitflag = types.iter()
         .map(|t| *t as i32)
@@ -83,6 +102,73 @@ impl Graph {
       }
     })
   }
+
+  /// Copy the current state of every device, signal and map visible to the graph.
+  /// 
+  /// See [GraphSnapshot](crate::snapshot::GraphSnapshot) for details.
+  pub fn snapshot(&self) -> crate::snapshot::GraphSnapshot {
+    crate::snapshot::GraphSnapshot::capture(self)
+  }
+
+  /// Render the current mapping topology as a Graphviz DOT graph.
+  /// 
+  /// This is shorthand for `graph.snapshot().to_dot()`, see [GraphSnapshot::to_dot](crate::snapshot::GraphSnapshot::to_dot).
+  pub fn export_dot(&self) -> String {
+    self.snapshot().to_dot()
+  }
+
+  /// Get all maps currently visible to the graph.
+  /// 
+  /// As with [get_devices](Graph::get_devices), the graph must be subscribed to maps (`mpr_type::MPR_MAP`) to see maps between remote devices.
+  pub fn get_maps(&self) -> Vec<Map> {
+    let list = unsafe {
+      mpr_graph_get_list(self.handle, mpr_type::MPR_MAP as i32)
+    };
+    read_list(list, |ptr| {
+      Map {
+        handle: ptr,
+        owned: false
+      }
+    })
+  }
+
+  /// Find a device visible to the graph by it's name, including the ordinal (e.g. `"device.1"`).
+  /// 
+  /// Like [get_devices](Graph::get_devices), this will only find devices the graph has subscribed to or owns.
+  pub fn find_device<'a>(&'a self, name: &str) -> Option<Device<'a>> {
+    let list = unsafe {
+      mpr_graph_get_list(self.handle, mpr_type::MPR_DEV as i32)
+    };
+    let list = crate::util::filter_by_name(list, name)?;
+    crate::util::first_in_list(list).map(|ptr| {
+      Device {
+        handle: ptr,
+        owned: false,
+        graph: Some(self)
+      }
+    })
+  }
+
+  /// Find a signal by it's full path, in the form `device.ordinal/signal` (e.g. `"synth.1/freq"`).
+  /// A leading `/` is allowed.
+  /// 
+  /// The graph must be subscribed to signals of the device for this to return a result.
+  /// 
+  /// # Examples
+  /// ```
+  /// use libmapper_rs::graph::Graph;
+  /// use libmapper_rs::constants::mpr_type;
+  /// let graph = Graph::create();
+  /// graph.subscribe(None, &[mpr_type::MPR_DEV, mpr_type::MPR_SIG]);
+  /// graph.poll_and_block(std::time::Duration::from_millis(100));
+  /// if let Some(sig) = graph.find_signal("synth.1/freq") {
+  ///   println!("Found signal with length {}", sig.get_vector_length());
+  /// }
+  /// ```
+  pub fn find_signal(&self, path: &str) -> Option<Signal> {
+    let (device, signal) = path.trim_start_matches('/').split_once('/')?;
+    self.find_device(device)?.signal(signal)
+  }
 }
 
 /// A directional connection between multiple signals. Changes to input signals will affect output signals.
@@ -113,11 +199,38 @@ pub struct Map {
 impl Map {
   /// Create a new map between two signals.
   /// This does not actually create the map in the graph, [push](Map::push) must be called to let the rest of the graph know about the map.
+  /// 
+  /// # Panics
+  /// Panics if libmapper fails to create the map. Use [try_create](Map::try_create) to handle this case.
   pub fn create(src: &Signal, dst: &Signal) -> Map {
-    Map {
-      handle: unsafe { mpr_map_new(1, &src.handle, 1, &dst.handle) },
-      owned: true
+    Map::try_create(src, dst).expect("mpr_map_new failed")
+  }
+
+  /// Create a new map between two signals, returning an error if libmapper fails to create it.
+  pub fn try_create(src: &Signal, dst: &Signal) -> Result<Map, Error> {
+    Map::try_create_from(&[src], &[dst])
+  }
+
+  /// Create a new map from any number of source signals to any number of destination signals.
+  /// 
+  /// As with [create](Map::create), [push](Map::push) must be called to publish the map.
+  /// Returns [`Error::CreationFailed`](Error::CreationFailed) if either slice is empty or libmapper rejects the map.
+  pub fn try_create_from(sources: &[&Signal], destinations: &[&Signal]) -> Result<Map, Error> {
+    if sources.is_empty() || destinations.is_empty() {
+      return Err(Error::CreationFailed);
     }
+    let sources: Vec<mpr_sig> = sources.iter().map(|s| s.handle).collect();
+    let destinations: Vec<mpr_sig> = destinations.iter().map(|s| s.handle).collect();
+    let handle = unsafe {
+      mpr_map_new(sources.len() as c_int, sources.as_ptr(), destinations.len() as c_int, destinations.as_ptr())
+    };
+    if handle.is_null() {
+      return Err(Error::CreationFailed);
+    }
+    Ok(Map {
+      handle,
+      owned: true
+    })
   }
 
   /// Publish this map to the distributed graph.
@@ -126,22 +239,92 @@ impl Map {
     unsafe {
       mpr_obj_push(self.handle);
     }
+    trace::event!(debug, map = %self.describe(), "pushed map");
   }
 
   /// Returns `true` once the map has been published and is active.
   /// Otherwise, returns false.
   pub fn is_ready(&self) -> bool {
-    unsafe {
+    let ready = unsafe {
       mpr_map_get_is_ready(self.handle) != 0
+    };
+    trace::event!(trace, map = %self.describe(), ready, "checked if map is ready");
+    ready
+  }
+
+  /// Publish this map and wait until it is active, or until `timeout` has elapsed.
+  /// 
+  /// While waiting, the local devices owning the map's signals are polled (or the map's graph, if none of them are local).
+  /// Returns [`Error::Timeout`](Error::Timeout) if the map did not become ready in time.
+  /// 
+  /// # Examples
+  /// ```
+  /// use std::time::Duration;
+  /// use libmapper_rs::graph::Map;
+  /// use libmapper_rs::signal::Signal;
+  /// fn connect(sig_a: &Signal, sig_b: &Signal) -> Map {
+  ///   let map = Map::create(sig_a, sig_b);
+  ///   map.push_and_wait(Duration::from_secs(5)).expect("map did not become ready");
+  ///   map
+  /// }
+  /// ```
+  pub fn push_and_wait(&self, timeout: Duration) -> Result<(), Error> {
+    let _span = trace::span!(DEBUG, "push_and_wait", map = %self.describe());
+    self.push();
+
+    let devices = self.local_devices();
+    let graph = unsafe { mpr_obj_get_graph(self.handle) };
+    let deadline = Instant::now() + timeout;
+    loop {
+      if self.is_ready() {
+        trace::event!(debug, "map ready");
+        return Ok(());
+      }
+      let remaining = deadline.saturating_duration_since(Instant::now());
+      if remaining.is_zero() {
+        trace::event!(warn, ?timeout, local_devices = devices.len(), "map did not become ready");
+        return Err(Error::Timeout);
+      }
+      let block = poll_share(remaining.min(WAIT_POLL_INTERVAL), devices.len()).as_millis() as c_int;
+      unsafe {
+        if devices.is_empty() {
+          mpr_graph_poll(graph, block);
+        } else {
+          for dev in &devices {
+            mpr_dev_poll(*dev, block);
+          }
+        }
+      }
     }
   }
 
+  /// Get the local devices that own the signals of this map, without duplicates.
+  fn local_devices(&self) -> Vec<mpr_dev> {
+    let list = unsafe { mpr_map_get_sigs(self.handle, mpr_loc::MPR_LOC_ANY) };
+    let mut devices: Vec<mpr_dev> = read_list(list, |sig| unsafe { mpr_sig_get_dev(sig) });
+    devices.retain(|dev| unsafe { mpr_obj_get_prop_as_int32(*dev, mpr_prop::MPR_PROP_IS_LOCAL, ptr::null()) } != 0);
+    devices.sort();
+    devices.dedup();
+    devices
+  }
+
   /// Destroy the map, severing the connection between the signals.
   pub fn release(self) {
     if !self.owned {
       return;
     }
 
+    trace::event!(debug, map = %self.describe(), "released map");
+    unsafe {
+      mpr_map_release(self.handle)
+    }
+  }
+
+  /// Remove this map from the distributed graph, severing the connection between the signals.
+  /// 
+  /// Unlike [release](Map::release), this also removes maps created by other processes, such as those returned by [Graph::get_maps].
+  pub fn unmap(self) {
+    trace::event!(debug, map = %self.describe(), "unmapped");
     unsafe {
       mpr_map_release(self.handle)
     }
@@ -150,7 +333,77 @@ impl Map {
   /// Set the expression used to map the values from the source(s) to the destination.
   ///
   /// This is a helper function wrapping [`MapperObject::set_property_str`]
+  /// 
+  /// # Panics
+  /// Panics if the expression contains a NUL byte. Use [try_set_expr](Map::try_set_expr) to handle this case.
   pub fn set_expr(&self, expression: &str) {
-    self.set_property_str(mpr_prop::MPR_PROP_EXPR, expression);
+    self.try_set_expr(expression).expect("expression contains a NUL byte");
+  }
+
+  /// Set the expression used to map the values from the source(s) to the destination,
+  /// returning [`Error::NulInString`](Error::NulInString) if the expression contains a NUL byte.
+  pub fn try_set_expr(&self, expression: &str) -> Result<(), Error> {
+    self.try_set_property_str(mpr_prop::MPR_PROP_EXPR, expression)
+  }
+
+  /// Get the expression used to map the values from the source(s) to the destination.
+  pub fn get_expr(&self) -> Option<String> {
+    self.get_property_str(mpr_prop::MPR_PROP_EXPR).ok()
+  }
+
+  /// Returns `true` if the map is muted, i.e. it is not currently sending updates to it's destination.
+  pub fn is_muted(&self) -> bool {
+    unsafe {
+      mpr_obj_get_prop_as_int32(self.handle, mpr_prop::MPR_PROP_MUTED, ptr::null()) != 0
+    }
+  }
+
+  /// Mute or unmute the map. Call [push](Map::push) to publish the change.
+  pub fn set_muted(&self, muted: bool) {
+    let muted = muted as c_int;
+    unsafe {
+      mpr_obj_set_prop(self.handle, mpr_prop::MPR_PROP_MUTED, ptr::null(), 1, mpr_type::MPR_BOOL, &muted as *const c_int as *const c_void, 1);
+    }
+  }
+
+  /// Get the network protocol used to send updates along this map.
+  pub fn get_protocol(&self) -> mpr_proto {
+    match self.get_property::<i32>(mpr_prop::MPR_PROP_PROTOCOL) {
+      Ok(1) => mpr_proto::MPR_PROTO_UDP,
+      Ok(2) => mpr_proto::MPR_PROTO_TCP,
+      _ => mpr_proto::MPR_PROTO_UNDEFINED
+    }
+  }
+
+  /// Set the network protocol used to send updates along this map. Call [push](Map::push) to publish the change.
+  pub fn set_protocol(&self, protocol: mpr_proto) {
+    self.set_property(mpr_prop::MPR_PROP_PROTOCOL, protocol as i32);
+  }
+
+  /// Get where the map's expression is evaluated: at the source (`MPR_LOC_SRC`) or destination (`MPR_LOC_DST`) device.
+  pub fn get_process_location(&self) -> mpr_loc {
+    match self.get_property::<i32>(mpr_prop::MPR_PROP_PROCESS_LOC) {
+      Ok(1) => mpr_loc::MPR_LOC_SRC,
+      Ok(2) => mpr_loc::MPR_LOC_DST,
+      _ => mpr_loc::MPR_LOC_UNDEFINED
+    }
+  }
+
+  /// Set where the map's expression is evaluated. Call [push](Map::push) to publish the change.
+  pub fn set_process_location(&self, location: mpr_loc) {
+    self.set_property(mpr_prop::MPR_PROP_PROCESS_LOC, location as i32);
+  }
+
+  /// Get the signals connected by this map at the given endpoint(s).
+  pub fn get_signals(&self, endpoint: mpr_loc) -> Vec<Signal> {
+    let list = unsafe { mpr_map_get_sigs(self.handle, endpoint) };
+    read_list(list, Signal::try_from_unowned).into_iter().filter_map(Result::ok).collect()
+  }
+
+  /// Describe the map as `src/sig -> dst/sig` for tracing events.
+  #[cfg(feature = "tracing")]
+  fn describe(&self) -> String {
+    let paths = |endpoint| self.get_signals(endpoint).iter().map(crate::snapshot::signal_path).collect::<Vec<_>>().join(", ");
+    format!("{} -> {}", paths(mpr_loc::MPR_LOC_SRC), paths(mpr_loc::MPR_LOC_DST))
   }
 }
\ No newline at end of file
@@ -24,13 +24,29 @@
 //!     // create signals, maps, etc.
 //! }
 //! ```
+//! 
+//! To avoid hanging forever if the network is unavailable, use [DeviceBuilder::build_and_wait](device::DeviceBuilder::build_and_wait) instead,
+//! which returns an error if the device doesn't become ready within a timeout.
 
-use bindings::mpr_get_version;
+use backend::mpr_get_version;
 
 pub mod device;
+pub mod error;
 pub mod graph;
+pub mod monitor;
 pub mod signal;
+pub mod snapshot;
+pub mod record;
+#[cfg(feature = "session")]
+pub mod session;
 pub mod object;
+#[cfg(feature = "osc-bridge")]
+pub mod osc_bridge;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod time;
+pub mod trace;
+pub mod version;
 
 mod util;
 
@@ -38,13 +54,34 @@ pub mod constants {
     pub use crate::bindings::mpr_dir;
     pub use crate::bindings::mpr_type;
     pub use crate::bindings::mpr_prop;
+    pub use crate::bindings::mpr_loc;
+    pub use crate::bindings::mpr_proto;
 }
+mod backend;
+#[cfg(not(generated_bindings))]
 mod bindings;
+#[cfg(generated_bindings)]
+#[allow(warnings)]
+mod bindings {
+    include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+}
+
+pub use error::Error;
 
 /// Get the version of the loaded libmapper library.
+/// 
+/// Use [MapperVersion::loaded](version::MapperVersion::loaded) to get the version as numbers.
+/// 
+/// # Panics
+/// Panics if the version string is not valid UTF-8. Use [try_get_mapper_version] to handle this case.
 pub fn get_mapper_version() -> &'static str {
+    try_get_mapper_version().expect("libmapper version is not valid UTF-8")
+}
+
+/// Get the version of the loaded libmapper library, returning an error if the version string is not valid UTF-8.
+pub fn try_get_mapper_version() -> Result<&'static str, Error> {
     unsafe {
         let version = mpr_get_version();
-        std::ffi::CStr::from_ptr::<'static>(version).to_str().unwrap()
+        Ok(std::ffi::CStr::from_ptr::<'static>(version).to_str()?)
     }
 }
\ No newline at end of file
@@ -0,0 +1,168 @@
+//! Watch output signals of other devices without modifying them.
+//!
+//! A [Monitor] creates a local input signal matching a remote signal, and maps the remote signal to it.
+//! When the monitor is dropped the map and local signal are removed, leaving the remote device as it was.
+//!
+//! The libmapper 2.4.9 bindings have no way to hide a device, so the local signals live on a device named
+//! `libmapper_rs_monitor`, which other peers (e.g. webmapper) will see while a monitor exists.
+use std::{marker::PhantomData, time::{Duration, Instant}};
+
+use crate::{backend::{mpr_dir, mpr_map_release}, device::{Device, MappableType}, error::Error, graph::{Graph, Map}, signal::{Signal, SignalError}, time::Timetag};
+
+/// Name of the local device created to host monitor signals.
+/// It is visible to other peers, so the name makes clear where it comes from.
+const MONITOR_DEVICE_NAME: &str = "libmapper_rs_monitor";
+
+/// An error that can occur when creating a [Monitor].
+#[derive(Debug, PartialEq)]
+pub enum MonitorError {
+    #[doc = "The path was not in the form `device/signal`."]
+    InvalidPath,
+    #[doc = "No signal with the given path is visible to the graph. Make sure the graph is subscribed to signals."]
+    SignalNotFound
+}
+
+impl std::fmt::Display for MonitorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MonitorError::InvalidPath => write!(f, "signal path is not in the form `device/signal`"),
+            MonitorError::SignalNotFound => write!(f, "signal not found in the graph")
+        }
+    }
+}
+
+/// Taps the value of a signal owned by another device.
+///
+/// # Examples
+/// Print every value sent by a remote signal:
+/// ```
+/// use std::time::Duration;
+/// use libmapper_rs::graph::Graph;
+/// use libmapper_rs::monitor::Monitor;
+/// use libmapper_rs::constants::mpr_type;
+/// let graph = Graph::create();
+/// graph.subscribe(None, &[mpr_type::MPR_DEV, mpr_type::MPR_SIG]);
+/// graph.poll_and_block(Duration::from_millis(100));
+/// if let Ok(mut monitor) = Monitor::create(&graph, "synth.1/freq") {
+///   for value in monitor.values::<f32>(Duration::from_millis(10), Duration::from_secs(1)).take(10) {
+///     match value {
+///       Ok((value, time)) => println!("{}: {:?}", time, value),
+///       Err(e) => println!("can't read value: {:?}", e)
+///     }
+///   }
+/// };
+/// ```
+pub struct Monitor<'a> {
+    map: Map,
+    pushed: bool,
+    local: LocalSignals<'a, Signal>
+}
+
+/// Signals created on a local device, together with that device.
+///
+/// Fields are dropped in declaration order, so `signals` are always freed before the `device` that owns them.
+pub(crate) struct LocalSignals<'a, S> {
+    pub(crate) signals: S,
+    pub(crate) device: Device<'a>
+}
+
+impl<'a> Monitor<'a> {
+    /// Start monitoring the signal at `path` (in the form `device.ordinal/signal`).
+    ///
+    /// The graph must already be subscribed to the remote device's signals.
+    /// The monitor's map is pushed once the local monitor device becomes ready, so [poll](Monitor::poll) must be called regularly.
+    pub fn create(graph: &'a Graph, path: &str) -> Result<Monitor<'a>, Error> {
+        let (_, name) = path.trim_start_matches('/').split_once('/').ok_or(MonitorError::InvalidPath)?;
+        let remote = graph.find_signal(path).ok_or(MonitorError::SignalNotFound)?;
+
+        let device = Device::try_create_from_graph(MONITOR_DEVICE_NAME, graph)?;
+        let signal = device.create_signal_of_type(name, mpr_dir::MPR_DIR_IN, remote.get_data_type(), remote.get_vector_length())?;
+        let map = Map::try_create(&remote, &signal)?;
+
+        Ok(Monitor {
+            map,
+            pushed: false,
+            local: LocalSignals {
+                signals: signal,
+                device
+            }
+        })
+    }
+
+    /// Poll the monitor's device, blocking for up to `time`.
+    /// Returns `true` if a new value was received from the remote signal.
+    pub fn poll(&mut self, time: Duration) -> bool {
+        self.local.device.poll_and_block(time);
+        if !self.pushed && self.local.device.is_ready() {
+            self.map.push();
+            self.pushed = true;
+        }
+        self.local.signals.get_status().was_set_remote()
+    }
+
+    /// Returns `true` once the map from the remote signal is active.
+    pub fn is_ready(&self) -> bool {
+        self.pushed && self.map.is_ready()
+    }
+
+    /// Get the most recent value received from the remote signal.
+    pub fn value<T: MappableType + Copy>(&self) -> Result<(Vec<T>, Timetag), SignalError> {
+        self.local.signals.get_value::<T>()
+    }
+
+    /// Get the local input signal that receives the remote values.
+    pub fn signal(&self) -> &Signal {
+        &self.local.signals
+    }
+
+    /// Iterate over every new value received from the remote signal, polling in `interval` steps.
+    ///
+    /// The iterator ends once no new value has been received for `timeout`.
+    /// Values that can't be read as `T` (e.g. because the signal has a different type) are yielded as errors.
+    pub fn values<T: MappableType + Copy>(&mut self, interval: Duration, timeout: Duration) -> Values<'_, 'a, T> {
+        Values {
+            monitor: self,
+            interval,
+            timeout,
+            _type: PhantomData
+        }
+    }
+}
+
+impl Drop for Monitor<'_> {
+    fn drop(&mut self) {
+        if self.pushed {
+            unsafe {
+                mpr_map_release(self.map.handle);
+            }
+            // let the release reach the remote device before the signal disappears
+            self.local.device.poll();
+        }
+    }
+}
+
+/// An iterator over values received by a [Monitor].
+/// Created by [Monitor::values].
+pub struct Values<'m, 'a, T> {
+    monitor: &'m mut Monitor<'a>,
+    interval: Duration,
+    timeout: Duration,
+    _type: PhantomData<T>
+}
+
+impl<T: MappableType + Copy> Iterator for Values<'_, '_, T> {
+    type Item = Result<(Vec<T>, Timetag), SignalError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return None;
+            }
+            if self.monitor.poll(self.interval.min(remaining)) {
+                return Some(self.monitor.value::<T>());
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use std::{ffi::c_void, ptr};
 
-use crate::{bindings::{mpr_obj, mpr_obj_get_prop_by_idx, mpr_obj_get_type, mpr_obj_set_prop, mpr_prop, mpr_type}, device::{Device, MappableType}, graph::Map, signal::Signal};
+use crate::{backend::{mpr_id, mpr_obj, mpr_obj_get_num_props, mpr_obj_get_prop_by_idx, mpr_obj_get_prop_by_key, mpr_obj_get_type, mpr_obj_push, mpr_obj_remove_prop, mpr_obj_set_prop, mpr_prop, mpr_type}, device::{Device, MappableType}, error::Error, graph::Map, signal::Signal, time::Timetag, trace};
 
 pub trait AsMprObject {
   fn as_mpr_object(&self) -> *mut c_void;
@@ -34,22 +34,84 @@ pub trait MapperObject {
   fn get_type(&self) -> mpr_type;
   /// Set a property on this object to a numerical value
   fn set_property<T: MappableType>(&self, property: mpr_prop, value: T);
+  /// Set a property on this object to a vector of numerical values
+  /// 
+  /// This is useful for properties such as `MPR_PROP_MIN` and `MPR_PROP_MAX` on vector signals,
+  /// where each element of the signal has it's own bound.
+  fn set_property_vec<T: MappableType>(&self, property: mpr_prop, values: &[T]);
   /// Set a property on this object to a string value
+  /// 
+  /// # Panics
+  /// Panics if `value` contains an interior NUL byte. Use [try_set_property_str](MapperObject::try_set_property_str) to handle this case.
   fn set_property_str(&self, property: mpr_prop, value: &str);
+  /// Set a property on this object to a string value.
+  /// Returns an error if `value` contains an interior NUL byte.
+  fn try_set_property_str(&self, property: mpr_prop, value: &str) -> Result<(), Error>;
 
   /// Get the value of a property by it's key from this object.
   /// If the property does not exist, or if the type is not matched, this function will return an error.
   fn get_property<T: MappableType + Copy>(&self, property: mpr_prop) -> Result<T, PropertyError>;
 
-  /// Get the value of a string property by it's key from this object.
+  /// Get the value of a vector property by it's key from this object.
+  /// The length of the returned `Vec` is the length of the stored property.
   /// If the property does not exist, or if the type is not matched, this function will return an error.
+  fn get_property_vec<T: MappableType + Copy>(&self, property: mpr_prop) -> Result<Vec<T>, PropertyError>;
+
+  /// Get the value of a string property by it's key from this object.
+  /// If the property does not exist, if the type is not matched, or if the string is not valid UTF-8, this function will return an error.
   fn get_property_str(&self, property: mpr_prop) -> Result<String, PropertyError>;
 
   /// Set a user-defined property to the specified value.
   /// The property is identified by a unique, case-sensitive string key.
   /// 
   /// If `publish` is true, the property will be published to other peers. Set to false if this property is only for local use.
+  /// 
+  /// # Panics
+  /// Panics if `property` contains an interior NUL byte. Use [try_set_custom_property](MapperObject::try_set_custom_property) to handle this case.
   fn set_custom_property<T: MappableType>(&self, property: &str, value: T, publish: bool);
+  /// Set a user-defined property to the specified value.
+  /// Returns an error if `property` contains an interior NUL byte.
+  fn try_set_custom_property<T: MappableType>(&self, property: &str, value: T, publish: bool) -> Result<(), Error>;
+
+  /// Set a user-defined property to the specified string value.
+  /// The property is identified by a unique, case-sensitive string key.
+  /// 
+  /// If `publish` is true, the property will be published to other peers. Set to false if this property is only for local use.
+  /// 
+  /// # Panics
+  /// Panics if `property` or `value` contain an interior NUL byte. Use [try_set_custom_property_str](MapperObject::try_set_custom_property_str) to handle this case.
+  fn set_custom_property_str(&self, property: &str, value: &str, publish: bool);
+  /// Set a user-defined property to the specified string value.
+  /// Returns an error if `property` or `value` contain an interior NUL byte.
+  fn try_set_custom_property_str(&self, property: &str, value: &str, publish: bool) -> Result<(), Error>;
+
+  /// Get the value of a user-defined property by it's key from this object.
+  /// If the property does not exist, or if the type is not matched, this function will return an error.
+  fn get_custom_property<T: MappableType + Copy>(&self, property: &str) -> Result<T, PropertyError>;
+
+  /// Get the value of a user-defined string property by it's key from this object.
+  /// If the property does not exist, if the type is not matched, or if the string is not valid UTF-8, this function will return an error.
+  fn get_custom_property_str(&self, property: &str) -> Result<String, PropertyError>;
+
+  /// Remove a user-defined property from this object.
+  /// Returns `true` if the property existed and was removed, and `false` otherwise (including if the key contains a NUL byte).
+  fn remove_custom_property(&self, property: &str) -> bool;
+
+  /// Get the unique ID of this object.
+  /// 
+  /// IDs are shared by all peers on the graph, so they can be used to identify the same device, signal or map across processes.
+  /// If the object does not have an ID yet, this will return an ID of `0`.
+  fn id(&self) -> MapperId;
+
+  /// Get every property of this object, including user-defined ones, keyed by name.
+  /// 
+  /// Properties that can't be represented as a [PropertyValue] (e.g. pointers and object lists) are skipped.
+  fn get_properties(&self) -> Vec<(String, PropertyValue)>;
+
+  /// Publish changes made to this object's properties to the distributed graph.
+  /// 
+  /// Local objects publish their changes when their device is polled; this is needed to change properties of objects owned by other processes.
+  fn push(&self);
 }
 
 impl<A> MapperObject for A where A: AsMprObject {
@@ -63,13 +125,27 @@ impl<A> MapperObject for A where A: AsMprObject {
     unsafe {
       mpr_obj_set_prop(self.as_mpr_object(), property, ptr::null(), 1, T::get_mpr_type(), &value as *const T as *const c_void, 1);
     }
+    trace::event!(debug, ?property, data_type = ?T::get_mpr_type(), "set property");
+  }
+
+  fn set_property_vec<T: MappableType>(&self, property: mpr_prop, values: &[T]) {
+    unsafe {
+      mpr_obj_set_prop(self.as_mpr_object(), property, ptr::null(), values.len() as i32, T::get_mpr_type(), values.as_ptr() as *const c_void, 1);
+    }
+    trace::event!(debug, ?property, data_type = ?T::get_mpr_type(), length = values.len(), "set property");
   }
 
   fn set_property_str(&self, property: mpr_prop, value: &str) {
-    let value_ptr = std::ffi::CString::new(value).expect("CString::new failed");
+    self.try_set_property_str(property, value).expect("CString::new failed");
+  }
+
+  fn try_set_property_str(&self, property: mpr_prop, value: &str) -> Result<(), Error> {
+    let value_ptr = std::ffi::CString::new(value)?;
     unsafe {
       mpr_obj_set_prop(self.as_mpr_object(), property, ptr::null(), 1, mpr_type::MPR_STR, value_ptr.as_ptr() as *const c_void, 1);
     }
+    trace::event!(debug, ?property, value, "set property");
+    Ok(())
   }
 
   fn get_property<T: MappableType + Copy>(&self, property: mpr_prop) -> Result<T, PropertyError> {
@@ -84,8 +160,27 @@ impl<A> MapperObject for A where A: AsMprObject {
       if actual_type != T::get_mpr_type() {
         return Err(PropertyError::TypeMismatch)
       }
+      // libmapper does not guarantee that property values are aligned (e.g. MPR_PROP_ID in 2.4.9)
+      let value = value as *const T;
+      Ok(ptr::read_unaligned(value))
+    }
+  }
+
+  fn get_property_vec<T: MappableType + Copy>(&self, property: mpr_prop) -> Result<Vec<T>, PropertyError> {
+    unsafe {
+      let mut actual_type: mpr_type = mpr_type::MPR_NULL;
+      let mut length: i32 = 0;
+      let mut value: *const c_void  = ptr::null();
+      mpr_obj_get_prop_by_idx(self.as_mpr_object(), property as i32,  ptr::null_mut(), &mut length, 
+      &mut actual_type, &mut value, ptr::null_mut());
+      if value.is_null() {
+        return Err(PropertyError::PropertyNotFound)
+      }
+      if actual_type != T::get_mpr_type() {
+        return Err(PropertyError::TypeMismatch)
+      }
       let value = value as *const T;
-      Ok(*value)
+      Ok((0..length as usize).map(|i| ptr::read_unaligned(value.add(i))).collect())
     }
   }
 
@@ -102,17 +197,201 @@ impl<A> MapperObject for A where A: AsMprObject {
         return Err(PropertyError::TypeMismatch)
       }
       let value = value as *const std::os::raw::c_char;
-      let value = std::ffi::CStr::from_ptr(value).to_str().unwrap().to_string();
+      let value = std::ffi::CStr::from_ptr(value).to_str().map_err(|_| PropertyError::InvalidString)?.to_string();
       Ok(value)
     }
   }
   
   fn set_custom_property<T: MappableType>(&self, property: &str, value: T, publish: bool) {
-      let property = std::ffi::CString::new(property).expect("CString::new failed");
+      self.try_set_custom_property(property, value, publish).expect("CString::new failed");
+  }
+
+  fn try_set_custom_property<T: MappableType>(&self, property: &str, value: T, publish: bool) -> Result<(), Error> {
+      let property = std::ffi::CString::new(property)?;
       unsafe {
         mpr_obj_set_prop(self.as_mpr_object(), mpr_prop::MPR_PROP_EXTRA, property.as_ptr() as *const i8, 
            1, T::get_mpr_type(), &value as *const T as *const c_void, publish.into());
       }
+      trace::event!(debug, key = ?property, data_type = ?T::get_mpr_type(), publish, "set custom property");
+      Ok(())
+  }
+
+  fn set_custom_property_str(&self, property: &str, value: &str, publish: bool) {
+      self.try_set_custom_property_str(property, value, publish).expect("CString::new failed");
+  }
+
+  fn try_set_custom_property_str(&self, property: &str, value: &str, publish: bool) -> Result<(), Error> {
+      let property = std::ffi::CString::new(property)?;
+      let value_ptr = std::ffi::CString::new(value)?;
+      unsafe {
+        mpr_obj_set_prop(self.as_mpr_object(), mpr_prop::MPR_PROP_EXTRA, property.as_ptr(), 
+           1, mpr_type::MPR_STR, value_ptr.as_ptr() as *const c_void, publish.into());
+      }
+      trace::event!(debug, key = ?property, value, publish, "set custom property");
+      Ok(())
+  }
+
+  fn get_custom_property<T: MappableType + Copy>(&self, property: &str) -> Result<T, PropertyError> {
+    let property = std::ffi::CString::new(property).map_err(|_| PropertyError::InvalidString)?;
+    unsafe {
+      let mut actual_type: mpr_type = mpr_type::MPR_NULL;
+      let mut value: *const c_void  = ptr::null();
+      mpr_obj_get_prop_by_key(self.as_mpr_object(), property.as_ptr(), ptr::null_mut(), 
+      &mut actual_type, &mut value, ptr::null_mut());
+      if value.is_null() {
+        return Err(PropertyError::PropertyNotFound)
+      }
+      if actual_type != T::get_mpr_type() {
+        return Err(PropertyError::TypeMismatch)
+      }
+      // libmapper does not guarantee that property values are aligned (e.g. MPR_PROP_ID in 2.4.9)
+      let value = value as *const T;
+      Ok(ptr::read_unaligned(value))
+    }
+  }
+
+  fn get_custom_property_str(&self, property: &str) -> Result<String, PropertyError> {
+    let property = std::ffi::CString::new(property).map_err(|_| PropertyError::InvalidString)?;
+    unsafe {
+      let mut actual_type: mpr_type = mpr_type::MPR_NULL;
+      let mut value: *const c_void  = ptr::null();
+      mpr_obj_get_prop_by_key(self.as_mpr_object(), property.as_ptr(), ptr::null_mut(), 
+      &mut actual_type, &mut value, ptr::null_mut());
+      if value.is_null() {
+        return Err(PropertyError::PropertyNotFound)
+      }
+      if actual_type != mpr_type::MPR_STR {
+        return Err(PropertyError::TypeMismatch)
+      }
+      let value = value as *const std::os::raw::c_char;
+      let value = std::ffi::CStr::from_ptr(value).to_str().map_err(|_| PropertyError::InvalidString)?.to_string();
+      Ok(value)
+    }
+  }
+
+  fn remove_custom_property(&self, property: &str) -> bool {
+    let Ok(property) = std::ffi::CString::new(property) else {
+      return false;
+    };
+    unsafe {
+      mpr_obj_remove_prop(self.as_mpr_object(), mpr_prop::MPR_PROP_EXTRA, property.as_ptr()) != 0
+    }
+  }
+
+  fn id(&self) -> MapperId {
+    MapperId(self.get_property::<i64>(mpr_prop::MPR_PROP_ID).unwrap_or(0) as mpr_id)
+  }
+
+  fn get_properties(&self) -> Vec<(String, PropertyValue)> {
+    let count = unsafe { mpr_obj_get_num_props(self.as_mpr_object(), 0) };
+    let mut properties = Vec::new();
+    for index in 0..count {
+      unsafe {
+        let mut key: *const std::os::raw::c_char = ptr::null();
+        let mut length: i32 = 0;
+        let mut actual_type: mpr_type = mpr_type::MPR_NULL;
+        let mut value: *const c_void = ptr::null();
+        mpr_obj_get_prop_by_idx(self.as_mpr_object(), index, &mut key, &mut length,
+          &mut actual_type, &mut value, ptr::null_mut());
+        if key.is_null() || value.is_null() {
+          continue;
+        }
+        let Ok(key) = std::ffi::CStr::from_ptr(key).to_str() else {
+          continue;
+        };
+        if let Some(value) = PropertyValue::read(actual_type, length.max(0) as usize, value) {
+          properties.push((key.to_string(), value));
+        }
+      }
+    }
+    properties
+  }
+
+  fn push(&self) {
+    unsafe {
+      mpr_obj_push(self.as_mpr_object());
+    }
+  }
+}
+
+/// The value of a property, as returned by [MapperObject::get_properties].
+/// 
+/// Every variant holds a vector, since properties may have a length greater than 1.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PropertyValue {
+  Bool(Vec<bool>),
+  Int32(Vec<i32>),
+  Int64(Vec<i64>),
+  Float(Vec<f32>),
+  Double(Vec<f64>),
+  String(Vec<String>),
+  Time(Vec<Timetag>),
+  /// libmapper data types (e.g. a signal's type), stored as their type character (`'f'`, `'i'`, ...).
+  Type(Vec<char>)
+}
+
+impl PropertyValue {
+  /// Copy a property value out of libmapper's storage.
+  unsafe fn read(data_type: mpr_type, length: usize, value: *const c_void) -> Option<PropertyValue> {
+    unsafe fn read_vec<T: Copy>(value: *const c_void, length: usize) -> Vec<T> {
+      let value = value as *const T;
+      (0..length).map(|i| ptr::read_unaligned(value.add(i))).collect()
+    }
+    Some(match data_type {
+      mpr_type::MPR_BOOL => PropertyValue::Bool(read_vec::<i32>(value, length).into_iter().map(|v| v != 0).collect()),
+      mpr_type::MPR_INT32 => PropertyValue::Int32(read_vec(value, length)),
+      mpr_type::MPR_INT64 => PropertyValue::Int64(read_vec(value, length)),
+      mpr_type::MPR_FLT => PropertyValue::Float(read_vec(value, length)),
+      mpr_type::MPR_DBL => PropertyValue::Double(read_vec(value, length)),
+      mpr_type::MPR_TIME => PropertyValue::Time(read_vec(value, length)),
+      mpr_type::MPR_TYPE => PropertyValue::Type(read_vec::<u32>(value, length).into_iter().filter_map(char::from_u32).collect()),
+      mpr_type::MPR_STR => {
+        // a single string is stored directly, multiple strings as an array of pointers
+        let strings = if length == 1 {
+          vec![value as *const std::os::raw::c_char]
+        } else {
+          read_vec::<*const std::os::raw::c_char>(value, length)
+        };
+        PropertyValue::String(strings.into_iter()
+          .filter(|s| !s.is_null())
+          .map(|s| std::ffi::CStr::from_ptr(s).to_string_lossy().into_owned())
+          .collect())
+      }
+      _ => return None
+    })
+  }
+}
+
+/// The unique identifier of a libmapper object (device, signal or map).
+/// 
+/// Returned by [MapperObject::id]. IDs are displayed in hexadecimal, matching libmapper's own output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+pub struct MapperId(pub(crate) mpr_id);
+
+impl MapperId {
+  /// Get the raw 64-bit value of this ID.
+  pub fn as_u64(&self) -> u64 {
+    self.0
+  }
+}
+
+impl From<u64> for MapperId {
+  fn from(value: u64) -> Self {
+    MapperId(value)
+  }
+}
+
+impl From<MapperId> for u64 {
+  fn from(value: MapperId) -> Self {
+    value.0
+  }
+}
+
+impl std::fmt::Display for MapperId {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{:#x}", self.0)
   }
 }
 
@@ -122,5 +401,17 @@ pub enum PropertyError {
   /// The property was not found on the object
   PropertyNotFound,
   /// The property was found, but the type did not match the expected type
-  TypeMismatch
+  TypeMismatch,
+  /// The property key contained a NUL byte, or the string value was not valid UTF-8
+  InvalidString
+}
+
+impl std::fmt::Display for PropertyError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      PropertyError::PropertyNotFound => write!(f, "property not found"),
+      PropertyError::TypeMismatch => write!(f, "property has a different type"),
+      PropertyError::InvalidString => write!(f, "property key or value is not a valid string")
+    }
+  }
 }
\ No newline at end of file
@@ -0,0 +1,366 @@
+//! Connect plain OSC clients to libmapper signals.
+//!
+//! An [OscBridge] listens on a UDP port and routes incoming OSC messages to local signals by address.
+//! Updates made locally to routed output signals are forwarded as OSC messages to every configured target.
+//!
+//! Only `int32` (`i`), `float` (`f`) and `double` (`d`) arguments are supported.
+//! Incoming arguments are converted to the signal's type, and outgoing messages use the signal's type,
+//! so `f32` signals send `f` arguments, `i32` signals send `i` arguments and `f64` signals send `d` arguments.
+//!
+//! This module requires the `osc-bridge` feature.
+//!
+//! # Examples
+//! ```no_run
+//! use std::time::Duration;
+//! use libmapper_rs::constants::mpr_dir;
+//! use libmapper_rs::device::Device;
+//! use libmapper_rs::osc_bridge::OscBridge;
+//! let dev = Device::create("bridge");
+//! let freq = dev.create_signal::<f32>("freq", mpr_dir::MPR_DIR_OUT);
+//! let level = dev.create_signal::<f32>("level", mpr_dir::MPR_DIR_IN);
+//!
+//! let mut bridge = OscBridge::bind("0.0.0.0:9000").unwrap();
+//! bridge.route("/synth/freq", freq);
+//! bridge.route("/meter/level", level);
+//! bridge.add_target("127.0.0.1:9001").unwrap();
+//! loop {
+//!     dev.poll_and_block(Duration::from_millis(10));
+//!     bridge.poll().unwrap();
+//! }
+//! ```
+use std::{fmt, io, net::{SocketAddr, ToSocketAddrs, UdpSocket}};
+
+use crate::{bindings::{mpr_dir, mpr_type}, error::Error, signal::Signal};
+
+/// The largest datagram the bridge will receive.
+const MAX_PACKET_SIZE: usize = 65507;
+/// How deeply bundles may be nested inside each other before the inner ones are dropped.
+const MAX_BUNDLE_DEPTH: usize = 8;
+
+/// An error that can occur when running an [OscBridge].
+#[derive(Debug, PartialEq)]
+pub enum OscError {
+    /// The socket could not be bound, or failed while sending or receiving.
+    Io(io::ErrorKind),
+    /// A target address could not be resolved.
+    InvalidAddress
+}
+
+impl fmt::Display for OscError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OscError::Io(kind) => write!(f, "OSC socket error: {}", kind),
+            OscError::InvalidAddress => write!(f, "invalid OSC target address")
+        }
+    }
+}
+
+/// A single OSC argument.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Float(f32),
+    Double(f64)
+}
+
+impl OscArg {
+    fn as_f64(self) -> f64 {
+        match self {
+            OscArg::Int(v) => v as f64,
+            OscArg::Float(v) => v as f64,
+            OscArg::Double(v) => v
+        }
+    }
+}
+
+/// An OSC address routed to a signal.
+struct Route {
+    address: String,
+    signal: Signal,
+    forward: bool
+}
+
+/// Routes OSC messages to and from local signals.
+pub struct OscBridge {
+    socket: UdpSocket,
+    routes: Vec<Route>,
+    targets: Vec<SocketAddr>,
+    buffer: Vec<u8>
+}
+
+impl OscBridge {
+    /// Listen for OSC messages on the given UDP address, e.g. `"0.0.0.0:9000"`.
+    pub fn bind(address: impl ToSocketAddrs) -> Result<OscBridge, Error> {
+        let socket = UdpSocket::bind(address).map_err(|e| OscError::Io(e.kind()))?;
+        socket.set_nonblocking(true).map_err(|e| OscError::Io(e.kind()))?;
+        Ok(OscBridge {
+            socket,
+            routes: Vec::new(),
+            targets: Vec::new(),
+            buffer: vec![0; MAX_PACKET_SIZE]
+        })
+    }
+
+    /// Get the address the bridge is listening on.
+    pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+        Ok(self.socket.local_addr().map_err(|e| OscError::Io(e.kind()))?)
+    }
+
+    /// Route an OSC address to a signal.
+    ///
+    /// Incoming messages sent to `address` set the signal's value.
+    /// If the signal is an output (`MPR_DIR_OUT`), local updates to it are also sent to every target as messages to `address`.
+    /// Use [Device::signal](crate::device::Device::signal) to route a signal you want to keep using elsewhere.
+    pub fn route(&mut self, address: &str, signal: Signal) {
+        let forward = signal.get_direction() == mpr_dir::MPR_DIR_OUT;
+        self.routes.push(Route {
+            address: address.to_string(),
+            signal,
+            forward
+        });
+    }
+
+    /// Add a UDP address that output signal updates are sent to.
+    pub fn add_target(&mut self, address: impl ToSocketAddrs) -> Result<(), Error> {
+        let address = address.to_socket_addrs().map_err(|_| OscError::InvalidAddress)?
+            .next().ok_or(OscError::InvalidAddress)?;
+        self.targets.push(address);
+        Ok(())
+    }
+
+    /// Handle every pending OSC message and forward local output updates, without blocking.
+    ///
+    /// Call this after polling the signals' device. Returns the number of incoming messages that set a signal value.
+    /// Messages with an unknown address, unsupported arguments or the wrong number of arguments are ignored.
+    ///
+    /// Forwarding relies on [Signal::get_status], so routed output signals should not have their status read elsewhere.
+    pub fn poll(&mut self) -> Result<usize, Error> {
+        let mut handled = 0;
+        loop {
+            let length = match self.socket.recv_from(&mut self.buffer) {
+                Ok((length, _)) => length,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(OscError::Io(e.kind()).into())
+            };
+            let mut messages = Vec::new();
+            decode_packet(&self.buffer[..length], &mut messages);
+            for (address, args) in messages {
+                if let Some(route) = self.routes.iter_mut().find(|r| r.address == address) {
+                    if set_signal(&mut route.signal, &args) {
+                        // don't echo values that came from OSC back out
+                        route.signal.get_status();
+                        handled += 1;
+                    }
+                }
+            }
+        }
+
+        for route in self.routes.iter().filter(|r| r.forward) {
+            if !route.signal.get_status().was_set_local() {
+                continue;
+            }
+            let Some(args) = read_signal(&route.signal) else {
+                continue;
+            };
+            let packet = encode_message(&route.address, &args);
+            for target in &self.targets {
+                self.socket.send_to(&packet, target).map_err(|e| OscError::Io(e.kind()))?;
+            }
+        }
+        Ok(handled)
+    }
+}
+
+/// Set a signal from OSC arguments, converting them to the signal's type.
+fn set_signal(signal: &mut Signal, args: &[OscArg]) -> bool {
+    let values = args.iter().map(|a| a.as_f64());
+    match signal.get_data_type() {
+        mpr_type::MPR_FLT => signal.set_value(&values.map(|v| v as f32).collect::<Vec<_>>()).is_ok(),
+        mpr_type::MPR_DBL => signal.set_value(&values.collect::<Vec<_>>()).is_ok(),
+        mpr_type::MPR_INT32 => signal.set_value(&values.map(|v| v as i32).collect::<Vec<_>>()).is_ok(),
+        _ => false
+    }
+}
+
+/// Read a signal's value as OSC arguments of the signal's type.
+fn read_signal(signal: &Signal) -> Option<Vec<OscArg>> {
+    match signal.get_data_type() {
+        mpr_type::MPR_FLT => signal.get_value::<f32>().ok().map(|(v, _)| v.into_iter().map(OscArg::Float).collect()),
+        mpr_type::MPR_DBL => signal.get_value::<f64>().ok().map(|(v, _)| v.into_iter().map(OscArg::Double).collect()),
+        mpr_type::MPR_INT32 => signal.get_value::<i32>().ok().map(|(v, _)| v.into_iter().map(OscArg::Int).collect()),
+        _ => None
+    }
+}
+
+/// Append an OSC string (NUL terminated, padded to 4 bytes).
+fn write_string(packet: &mut Vec<u8>, s: &str) {
+    packet.extend_from_slice(s.as_bytes());
+    let padding = 4 - s.len() % 4;
+    packet.resize(packet.len() + padding, 0);
+}
+
+/// Encode an OSC message.
+pub fn encode_message(address: &str, args: &[OscArg]) -> Vec<u8> {
+    let mut packet = Vec::new();
+    write_string(&mut packet, address);
+    let tags: String = std::iter::once(',').chain(args.iter().map(|a| match a {
+        OscArg::Int(_) => 'i',
+        OscArg::Float(_) => 'f',
+        OscArg::Double(_) => 'd'
+    })).collect();
+    write_string(&mut packet, &tags);
+    for arg in args {
+        match arg {
+            OscArg::Int(v) => packet.extend_from_slice(&v.to_be_bytes()),
+            OscArg::Float(v) => packet.extend_from_slice(&v.to_be_bytes()),
+            OscArg::Double(v) => packet.extend_from_slice(&v.to_be_bytes())
+        }
+    }
+    packet
+}
+
+/// Read an OSC string, returning it and the rest of the packet.
+fn read_string(data: &[u8]) -> Option<(&str, &[u8])> {
+    let end = data.iter().position(|b| *b == 0)?;
+    let s = std::str::from_utf8(&data[..end]).ok()?;
+    let padded = (end / 4 + 1) * 4;
+    Some((s, data.get(padded..)?))
+}
+
+/// Decode an OSC message or bundle, appending every message it contains to `messages`.
+/// Malformed packets, and bundles nested more than 8 levels deep, are skipped.
+pub fn decode_packet(data: &[u8], messages: &mut Vec<(String, Vec<OscArg>)>) {
+    decode_element(data, messages, 0);
+}
+
+fn decode_element(data: &[u8], messages: &mut Vec<(String, Vec<OscArg>)>, depth: usize) {
+    if let Some(mut rest) = data.strip_prefix(b"#bundle\0") {
+        if depth >= MAX_BUNDLE_DEPTH {
+            return;
+        }
+        // skip the bundle's timetag, elements are delivered immediately
+        rest = rest.get(8..).unwrap_or_default();
+        while rest.len() >= 4 {
+            let size = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+            let Some(end) = size.checked_add(4) else {
+                return;
+            };
+            let Some(element) = rest.get(4..end) else {
+                return;
+            };
+            decode_element(element, messages, depth + 1);
+            rest = &rest[end..];
+        }
+        return;
+    }
+    if let Some(message) = decode_message(data) {
+        messages.push(message);
+    }
+}
+
+fn decode_message(data: &[u8]) -> Option<(String, Vec<OscArg>)> {
+    let (address, rest) = read_string(data)?;
+    if !address.starts_with('/') {
+        return None;
+    }
+    let (tags, mut rest) = read_string(rest)?;
+    let mut args = Vec::new();
+    for tag in tags.strip_prefix(',')?.chars() {
+        let arg = match tag {
+            'i' => OscArg::Int(i32::from_be_bytes(rest.get(..4)?.try_into().ok()?)),
+            'f' => OscArg::Float(f32::from_be_bytes(rest.get(..4)?.try_into().ok()?)),
+            'd' => OscArg::Double(f64::from_be_bytes(rest.get(..8)?.try_into().ok()?)),
+            _ => return None
+        };
+        rest = &rest[if tag == 'd' { 8 } else { 4 }..];
+        args.push(arg);
+    }
+    Some((address.to_string(), args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(data: &[u8]) -> Vec<(String, Vec<OscArg>)> {
+        let mut messages = Vec::new();
+        decode_packet(data, &mut messages);
+        messages
+    }
+
+    /// Wrap `elements` in a bundle with an immediate timetag.
+    fn bundle(elements: &[Vec<u8>]) -> Vec<u8> {
+        let mut packet = b"#bundle\0".to_vec();
+        packet.extend_from_slice(&1u64.to_be_bytes());
+        for element in elements {
+            packet.extend_from_slice(&(element.len() as u32).to_be_bytes());
+            packet.extend_from_slice(element);
+        }
+        packet
+    }
+
+    #[test]
+    fn pads_addresses() {
+        assert_eq!(encode_message("/a", &[]), b"/a\0\0,\0\0\0");
+        assert_eq!(encode_message("/ab", &[]), b"/ab\0,\0\0\0");
+        assert_eq!(encode_message("/abc", &[]), b"/abc\0\0\0\0,\0\0\0");
+        for address in ["/a", "/ab", "/abc"] {
+            assert_eq!(decode(&encode_message(address, &[])), vec![(address.to_string(), vec![])]);
+        }
+    }
+
+    #[test]
+    fn encodes_arguments() {
+        let args = [OscArg::Int(-7), OscArg::Float(0.5), OscArg::Double(1e100)];
+        let packet = encode_message("/x", &args);
+        let mut expected = b"/x\0\0,ifd\0\0\0\0".to_vec();
+        expected.extend_from_slice(&(-7i32).to_be_bytes());
+        expected.extend_from_slice(&0.5f32.to_be_bytes());
+        expected.extend_from_slice(&1e100f64.to_be_bytes());
+        assert_eq!(packet, expected);
+        assert_eq!(decode(&packet), vec![("/x".to_string(), args.to_vec())]);
+    }
+
+    #[test]
+    fn decodes_bundles() {
+        let first = encode_message("/first", &[OscArg::Int(1)]);
+        let second = encode_message("/second", &[OscArg::Float(2.0)]);
+        let nested = bundle(&[bundle(&[first]), second]);
+        assert_eq!(decode(&nested), vec![
+            ("/first".to_string(), vec![OscArg::Int(1)]),
+            ("/second".to_string(), vec![OscArg::Float(2.0)])
+        ]);
+    }
+
+    #[test]
+    fn limits_bundle_depth() {
+        let mut packet = encode_message("/deep", &[]);
+        for _ in 0..MAX_BUNDLE_DEPTH {
+            packet = bundle(&[packet]);
+        }
+        assert_eq!(decode(&packet).len(), 1);
+        assert!(decode(&bundle(&[packet])).is_empty());
+    }
+
+    #[test]
+    fn skips_truncated_packets() {
+        let packet = encode_message("/x", &[OscArg::Double(1.0)]);
+        for length in 0..packet.len() {
+            assert!(decode(&packet[..length]).is_empty(), "decoded {} of {} bytes", length, packet.len());
+        }
+        // an element size that runs past the end of the bundle, or overflows
+        let mut packet = bundle(&[]);
+        packet.extend_from_slice(&u32::MAX.to_be_bytes());
+        packet.extend_from_slice(b"/x\0\0,\0\0\0");
+        assert!(decode(&packet).is_empty());
+    }
+
+    #[test]
+    fn skips_unknown_tags() {
+        let mut packet = b"/x\0\0,s\0\0".to_vec();
+        packet.extend_from_slice(b"hi\0\0");
+        assert!(decode(&packet).is_empty());
+        assert!(decode(b"/x\0\0i\0\0\0\0\0\0\x01").is_empty());
+        assert!(decode(b"x\0\0\0,\0\0\0").is_empty());
+    }
+}
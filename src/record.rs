@@ -0,0 +1,485 @@
+//! Record signal updates to a file and play them back later.
+//!
+//! A [Recorder] taps a set of signals (the same way a [Monitor](crate::monitor::Monitor) does) and stores every update
+//! it receives, along with it's timetag and instance id, in a [Recording].
+//! Recordings are saved as CSV, with one update per line: `time,signal,instance,value...`,
+//! where `time` is the raw NTP timetag and `signal` is the full `device/signal` path.
+//!
+//! A [Player] sends the values in a recording from local output signals, keeping their original relative timing.
+//!
+//! # Examples
+//! ```no_run
+//! use std::time::{Duration, Instant};
+//! use libmapper_rs::constants::mpr_type;
+//! use libmapper_rs::device::Device;
+//! use libmapper_rs::graph::Graph;
+//! use libmapper_rs::record::{Player, Recorder};
+//! let graph = Graph::create();
+//! graph.subscribe(None, &[mpr_type::MPR_DEV, mpr_type::MPR_SIG]);
+//! graph.poll_and_block(Duration::from_secs(1));
+//!
+//! let mut recorder = Recorder::create(&graph, &["synth.1/freq", "synth.1/gain"]).unwrap();
+//! let start = Instant::now();
+//! while start.elapsed() < Duration::from_secs(60) {
+//!     recorder.poll(Duration::from_millis(10));
+//! }
+//! let recording = recorder.finish();
+//! recording.save("rehearsal.csv").unwrap();
+//!
+//! let device = Device::create("playback");
+//! let mut player = Player::new(recording, &device).unwrap().speed(2.0).unwrap();
+//! player.play(&device);
+//! ```
+use std::{ffi::{c_int, c_void}, fmt, fs::File, io::{self, BufRead, BufReader, BufWriter, Write}, path::Path, ptr, time::{Duration, Instant}};
+
+use crate::{backend::{mpr_dir, mpr_id, mpr_map_release, mpr_obj_get_prop_as_ptr, mpr_obj_set_prop, mpr_prop, mpr_sig, mpr_sig_evt, mpr_sig_set_cb, mpr_sig_set_value, mpr_time, mpr_type}, device::{Device, WAIT_POLL_INTERVAL}, error::Error, graph::{Graph, Map}, monitor::LocalSignals, object::MapperObject, signal::Signal, time::Timetag};
+
+/// Name of the local device created to host recorder signals.
+/// It is visible to other peers, so the name makes clear where it comes from.
+const RECORDER_DEVICE_NAME: &str = "libmapper_rs_recorder";
+
+/// An error that can occur when reading or writing a recording.
+#[derive(Debug, PartialEq)]
+pub enum RecordError {
+    /// The recording could not be read or written.
+    Io(io::ErrorKind),
+    /// A line of the recording could not be parsed. Contains the 1-based line number.
+    InvalidLine(usize),
+    /// A path passed to [Recorder::create] was not in the form `device/signal`.
+    InvalidPath(String),
+    /// A signal passed to [Recorder::create] could not be found in the graph.
+    SignalNotFound(String),
+    /// A [Player] speed was not a finite number greater than zero.
+    InvalidSpeed(f64)
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::Io(kind) => write!(f, "failed to access recording: {}", kind),
+            RecordError::InvalidLine(line) => write!(f, "invalid recording on line {}", line),
+            RecordError::InvalidPath(path) => write!(f, "'{}' is not a `device/signal` path", path),
+            RecordError::SignalNotFound(path) => write!(f, "signal '{}' not found", path),
+            RecordError::InvalidSpeed(speed) => write!(f, "invalid playback speed {}, must be finite and greater than zero", speed)
+        }
+    }
+}
+
+/// A single signal update in a [Recording].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedEvent {
+    /// The timetag the update was sent with.
+    pub time: Timetag,
+    /// The index of the updated signal in [Recording::signals].
+    pub signal: usize,
+    /// The id of the updated instance, `0` for signals without instances.
+    pub instance: u64,
+    pub value: Vec<f64>
+}
+
+/// A list of signal updates, sorted by time.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Recording {
+    /// The full `device/signal` paths of the recorded signals.
+    pub signals: Vec<String>,
+    pub events: Vec<RecordedEvent>
+}
+
+impl Recording {
+    /// Get the time between the first and last event.
+    pub fn duration(&self) -> Duration {
+        match (self.events.first(), self.events.last()) {
+            (Some(first), Some(last)) if last.time > first.time => (last.time - first.time).into(),
+            _ => Duration::ZERO
+        }
+    }
+
+    /// Write the recording as CSV.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "time,signal,instance,value")?;
+        for event in &self.events {
+            write!(writer, "{},{},{}", event.time.as_raw(), self.signals[event.signal], event.instance)?;
+            for value in &event.value {
+                write!(writer, ",{}", value)?;
+            }
+            writeln!(writer)?;
+        }
+        writer.flush()
+    }
+
+    /// Read a recording written by [write_csv](Recording::write_csv).
+    pub fn read_csv<R: BufRead>(reader: R) -> Result<Recording, Error> {
+        let mut recording = Recording::default();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| RecordError::Io(e.kind()))?;
+            if index == 0 || line.is_empty() {
+                continue;
+            }
+            let invalid = || RecordError::InvalidLine(index + 1);
+            let mut fields = line.split(',');
+            let time = fields.next().and_then(|t| t.parse::<u64>().ok()).ok_or_else(invalid)?;
+            let path = fields.next().ok_or_else(invalid)?;
+            let instance = fields.next().and_then(|i| i.parse::<u64>().ok()).ok_or_else(invalid)?;
+            let value = fields.map(|v| v.parse::<f64>()).collect::<Result<Vec<_>, _>>().map_err(|_| invalid())?;
+
+            let signal = match recording.signals.iter().position(|s| s == path) {
+                Some(signal) => signal,
+                None => {
+                    recording.signals.push(path.to_string());
+                    recording.signals.len() - 1
+                }
+            };
+            recording.events.push(RecordedEvent {
+                time: Timetag::from_raw(time),
+                signal,
+                instance,
+                value
+            });
+        }
+        // updates from different devices can arrive out of order
+        recording.events.sort_by_key(|e| e.time);
+        Ok(recording)
+    }
+
+    /// Write the recording to a CSV file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let file = File::create(path).map_err(|e| RecordError::Io(e.kind()))?;
+        self.write_csv(BufWriter::new(file)).map_err(|e| RecordError::Io(e.kind()))?;
+        Ok(())
+    }
+
+    /// Read a recording from a CSV file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Recording, Error> {
+        let file = File::open(path).map_err(|e| RecordError::Io(e.kind()))?;
+        Recording::read_csv(BufReader::new(file))
+    }
+}
+
+/// Passed to libmapper as the `MPR_PROP_DATA` of a recorder signal.
+struct TapContext {
+    signal: usize,
+    events: *mut Vec<RecordedEvent>
+}
+
+/// A recorder signal mapped from the signal being recorded.
+struct Tap {
+    map: Map,
+    signal: Signal,
+    // only read by libmapper through `MPR_PROP_DATA`
+    _context: Box<TapContext>
+}
+
+/// Records every update of a set of signals.
+///
+/// Like a [Monitor](crate::monitor::Monitor), the recorder creates a local device with one input signal per recorded signal,
+/// and maps each recorded signal to it. The maps are removed when the recorder is finished or dropped.
+pub struct Recorder<'a> {
+    local: LocalSignals<'a, Vec<Tap>>,
+    // boxed so that the address given to libmapper stays valid when the recorder is moved
+    #[allow(clippy::box_collection)]
+    events: Box<Vec<RecordedEvent>>,
+    signals: Vec<String>,
+    pushed: bool
+}
+
+impl<'a> Recorder<'a> {
+    /// Start recording the signals at `paths` (in the form `device.ordinal/signal`).
+    ///
+    /// The graph must already be subscribed to the signals' devices.
+    /// The maps are pushed once the local recorder device becomes ready, so [poll](Recorder::poll) must be called regularly.
+    /// A path listed more than once is only recorded once.
+    pub fn create(graph: &'a Graph, paths: &[&str]) -> Result<Recorder<'a>, Error> {
+        let device = Device::try_create_from_graph(RECORDER_DEVICE_NAME, graph)?;
+        let mut recorder = Recorder {
+            local: LocalSignals {
+                signals: Vec::with_capacity(paths.len()),
+                device
+            },
+            events: Box::default(),
+            signals: Vec::with_capacity(paths.len()),
+            pushed: false
+        };
+        for path in paths {
+            let path = path.trim_start_matches('/');
+            if !path.contains('/') {
+                return Err(RecordError::InvalidPath(path.to_string()).into());
+            }
+            if recorder.signals.iter().any(|s| s == path) {
+                continue;
+            }
+            let remote = graph.find_signal(path).ok_or_else(|| RecordError::SignalNotFound(path.to_string()))?;
+
+            // named after the full path, since signals with the same name on different devices can be recorded together
+            let signal = recorder.local.device.create_signal_of_type(path, mpr_dir::MPR_DIR_IN, remote.get_data_type(), remote.get_vector_length())?;
+            let instances = remote.get_property::<i32>(mpr_prop::MPR_PROP_NUM_INST).unwrap_or(1);
+            if instances > 1 {
+                signal.reserve_instances(instances as u32 - 1);
+            }
+            let context = Box::new(TapContext {
+                signal: recorder.signals.len(),
+                events: &mut *recorder.events
+            });
+            unsafe {
+                mpr_obj_set_prop(signal.handle, mpr_prop::MPR_PROP_DATA, ptr::null(), 1, mpr_type::MPR_PTR,
+                    &*context as *const TapContext as *const c_void, 0);
+                mpr_sig_set_cb(signal.handle, Some(record_trampoline), mpr_sig_evt::MPR_SIG_UPDATE as c_int);
+            }
+            let map = Map::try_create(&remote, &signal)?;
+            recorder.signals.push(path.to_string());
+            recorder.local.signals.push(Tap { map, signal, _context: context });
+        }
+        Ok(recorder)
+    }
+
+    /// Poll the recorder's device, blocking for up to `time`.
+    /// Returns the number of updates recorded during this call.
+    pub fn poll(&mut self, time: Duration) -> usize {
+        let before = self.events.len();
+        self.local.device.poll_and_block(time);
+        if !self.pushed && self.local.device.is_ready() {
+            for tap in &self.local.signals {
+                tap.map.push();
+            }
+            self.pushed = true;
+        }
+        self.events.len() - before
+    }
+
+    /// Returns `true` once every map to the recorder is active.
+    pub fn is_ready(&self) -> bool {
+        self.pushed && self.local.signals.iter().all(|tap| tap.map.is_ready())
+    }
+
+    /// Get the updates recorded so far.
+    pub fn events(&self) -> &[RecordedEvent] {
+        &self.events
+    }
+
+    /// Stop recording, returning everything that was recorded.
+    pub fn finish(mut self) -> Recording {
+        let mut events = std::mem::take(&mut *self.events);
+        // updates from different devices can arrive out of order
+        events.sort_by_key(|e| e.time);
+        Recording {
+            signals: std::mem::take(&mut self.signals),
+            events
+        }
+    }
+}
+
+impl Drop for Recorder<'_> {
+    fn drop(&mut self) {
+        for tap in &self.local.signals {
+            unsafe {
+                // make sure libmapper can't call into the context after it's freed
+                mpr_sig_set_cb(tap.signal.handle, None, 0);
+                mpr_obj_set_prop(tap.signal.handle, mpr_prop::MPR_PROP_DATA, ptr::null(), 1, mpr_type::MPR_PTR, ptr::null(), 0);
+                if self.pushed {
+                    mpr_map_release(tap.map.handle);
+                }
+            }
+        }
+        if self.pushed {
+            // let the release reach the remote devices before the signals disappear
+            self.local.device.poll();
+        }
+    }
+}
+
+/// Appends `MPR_SIG_UPDATE` events to the recording referenced by the signal's `MPR_PROP_DATA`.
+unsafe extern "C" fn record_trampoline(signal: mpr_sig, event: mpr_sig_evt, instance: mpr_id, length: c_int,
+    data_type: mpr_type, value: *const c_void, time: mpr_time) {
+    if event != mpr_sig_evt::MPR_SIG_UPDATE || value.is_null() {
+        return;
+    }
+    let context = mpr_obj_get_prop_as_ptr(signal, mpr_prop::MPR_PROP_DATA, ptr::null()) as *const TapContext;
+    let Some(context) = context.as_ref() else {
+        return;
+    };
+    let length = length.max(0) as usize;
+    let value = match data_type {
+        mpr_type::MPR_FLT => std::slice::from_raw_parts(value as *const f32, length).iter().map(|v| *v as f64).collect(),
+        mpr_type::MPR_DBL => std::slice::from_raw_parts(value as *const f64, length).to_vec(),
+        mpr_type::MPR_INT32 => std::slice::from_raw_parts(value as *const i32, length).iter().map(|v| *v as f64).collect(),
+        _ => return
+    };
+    if let Some(events) = context.events.as_mut() {
+        events.push(RecordedEvent {
+            time: Timetag::from_raw(time),
+            signal: context.signal,
+            instance,
+            value
+        });
+    }
+}
+
+/// Plays a [Recording] back through local output signals.
+pub struct Player {
+    recording: Recording,
+    signals: Vec<Signal>,
+    speed: f64
+}
+
+impl Player {
+    /// Prepare to play `recording` from `device`.
+    ///
+    /// Each recorded signal is played from the signal on `device` with the same name (ignoring the recorded device name).
+    /// If the device has no such signal, a `f64` output signal with the recorded vector length is created.
+    pub fn new(recording: Recording, device: &Device) -> Result<Player, Error> {
+        let mut signals = Vec::with_capacity(recording.signals.len());
+        for (index, path) in recording.signals.iter().enumerate() {
+            let name = path.split_once('/').map_or(path.as_str(), |(_, name)| name);
+            let signal = match device.signal(name) {
+                Some(signal) => signal,
+                None => {
+                    let length = recording.events.iter().find(|e| e.signal == index).map_or(1, |e| e.value.len());
+                    device.create_signal_of_type(name, mpr_dir::MPR_DIR_OUT, mpr_type::MPR_DBL, length as u32)?
+                }
+            };
+            signals.push(signal);
+        }
+        Ok(Player {
+            recording,
+            signals,
+            speed: 1.0
+        })
+    }
+
+    /// Set the playback speed, e.g. `2.0` to play twice as fast. Defaults to `1.0`.
+    ///
+    /// Returns [`RecordError::InvalidSpeed`](RecordError::InvalidSpeed) if `speed` is not a finite number greater than zero.
+    pub fn speed(mut self, speed: f64) -> Result<Player, Error> {
+        if !(speed.is_finite() && speed > 0.0) {
+            return Err(RecordError::InvalidSpeed(speed).into());
+        }
+        self.speed = speed;
+        Ok(self)
+    }
+
+    /// Get the recording being played.
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    /// Play the whole recording, blocking until the last update has been sent.
+    ///
+    /// `device` must be the device passed to [new](Player::new); it is polled while waiting between updates.
+    /// Events are played in the order they appear in the recording, events timed before the first one are sent immediately.
+    pub fn play(&mut self, device: &Device) {
+        let Some(first) = self.recording.events.first().map(|e| e.time) else {
+            return;
+        };
+        let start = Instant::now();
+        for event in &self.recording.events {
+            // timetag subtraction wraps, so don't subtract from an earlier time
+            let offset = if event.time > first { f64::from(event.time - first) / self.speed } else { 0.0 };
+            let due = start + Duration::from_secs_f64(offset);
+            loop {
+                let remaining = due.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    break;
+                }
+                device.poll_and_block(remaining.min(WAIT_POLL_INTERVAL));
+            }
+            let signal = &self.signals[event.signal];
+            if event.value.len() == signal.get_vector_length() as usize {
+                unsafe {
+                    // libmapper converts the values to the signal's type
+                    mpr_sig_set_value(signal.handle, event.instance, event.value.len() as c_int, mpr_type::MPR_DBL,
+                        event.value.as_ptr() as *const c_void);
+                }
+            }
+        }
+        device.poll();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recording() -> Recording {
+        Recording {
+            signals: vec!["synth.1/freq".to_string(), "synth.1/position".to_string()],
+            events: vec![
+                RecordedEvent { time: Timetag::from_secs_f64(1.0), signal: 0, instance: 0, value: vec![440.0] },
+                RecordedEvent { time: Timetag::from_secs_f64(1.5), signal: 1, instance: 3, value: vec![0.25, -1.5, 1e-9] },
+                RecordedEvent { time: Timetag::from_secs_f64(2.0), signal: 0, instance: 0, value: vec![220.125] }
+            ]
+        }
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let recording = recording();
+        let mut csv = Vec::new();
+        recording.write_csv(&mut csv).unwrap();
+        assert_eq!(Recording::read_csv(csv.as_slice()).unwrap(), recording);
+        assert_eq!(recording.duration(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn read_csv_sorts_by_time() {
+        let csv = format!("time,signal,instance,value\n{},a.1/x,0,2\n{},a.1/x,0,1\n",
+            Timetag::from_secs_f64(2.0).as_raw(), Timetag::from_secs_f64(1.0).as_raw());
+        let recording = Recording::read_csv(csv.as_bytes()).unwrap();
+        let values: Vec<f64> = recording.events.iter().map(|e| e.value[0]).collect();
+        assert_eq!(values, vec![1.0, 2.0]);
+        assert_eq!(recording.duration(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn read_csv_reports_invalid_line() {
+        let csv = "time,signal,instance,value\n1,a.1/x,0,1\nnot a time,a.1/x,0,1\n";
+        assert!(matches!(Recording::read_csv(csv.as_bytes()), Err(Error::Record(RecordError::InvalidLine(3)))));
+    }
+
+    #[test]
+    fn records_same_named_signals_from_different_devices() {
+        let graph = Graph::create();
+        let synth = Device::create_from_graph("rs_record_synth", &graph);
+        let drums = Device::create_from_graph("rs_record_drums", &graph);
+        let mut synth_gain = synth.create_signal::<f32>("gain", mpr_dir::MPR_DIR_OUT);
+        let mut drums_gain = drums.create_signal::<f32>("gain", mpr_dir::MPR_DIR_OUT);
+        synth.poll();
+        drums.poll();
+        graph.subscribe(None, &[mpr_type::MPR_DEV, mpr_type::MPR_SIG]);
+
+        let mut recorder = Recorder::create(&graph, &["rs_record_synth.1/gain", "rs_record_drums.1/gain"]).unwrap();
+        let names: Vec<String> = recorder.local.device.get_signals(mpr_dir::MPR_DIR_IN).iter()
+            .map(|s| s.get_property_str(mpr_prop::MPR_PROP_NAME).unwrap())
+            .collect();
+        assert_eq!(names, vec!["rs_record_synth.1/gain", "rs_record_drums.1/gain"]);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !recorder.is_ready() {
+            assert!(Instant::now() < deadline, "recorder did not become ready");
+            recorder.poll(Duration::from_millis(1));
+        }
+        synth_gain.set_value_scalar(&0.25f32).unwrap();
+        drums_gain.set_value_scalar(&0.75f32).unwrap();
+        synth.poll();
+        drums.poll();
+        assert_eq!(recorder.poll(Duration::from_millis(1)), 2);
+
+        let recording = recorder.finish();
+        let mut recorded: Vec<(&str, f64)> = recording.events.iter()
+            .map(|e| (recording.signals[e.signal].as_str(), e.value[0]))
+            .collect();
+        recorded.sort_by(|a, b| a.0.cmp(b.0));
+        assert_eq!(recorded, vec![("rs_record_drums.1/gain", 0.75), ("rs_record_synth.1/gain", 0.25)]);
+    }
+
+    #[test]
+    fn rejects_invalid_speeds() {
+        let player = || Player {
+            recording: Recording::default(),
+            signals: Vec::new(),
+            speed: 1.0
+        };
+        for speed in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(player().speed(speed), Err(Error::Record(RecordError::InvalidSpeed(_)))), "accepted speed {}", speed);
+        }
+        assert_eq!(player().speed(0.5).map(|p| p.speed).ok(), Some(0.5));
+    }
+}
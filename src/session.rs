@@ -0,0 +1,358 @@
+//! Saving and restoring the maps between a set of devices.
+//!
+//! A [Session] is a list of map descriptions, with endpoints identified by their `device/signal` path.
+//! Sessions are stored as JSON in the same layout as [webmapper](https://github.com/libmapper/webmapper)'s
+//! session files, so a patch made in webmapper can be loaded from rust and vice versa.
+//!
+//! This module requires the `session` feature.
+//!
+//! # Examples
+//! ```no_run
+//! use std::time::Duration;
+//! use libmapper_rs::graph::Graph;
+//! use libmapper_rs::session::Session;
+//! let graph = Graph::create();
+//! let session = Session::load("rehearsal.json").expect("failed to load session");
+//! let maps = session.restore(&graph, Duration::from_secs(10));
+//! println!("restored {} of {} maps", maps.len(), session.maps.len());
+//! ```
+use std::{fmt, fs, io, path::Path, time::{Duration, Instant}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{bindings::{mpr_loc, mpr_prop, mpr_proto, mpr_type}, device::WAIT_POLL_INTERVAL, error::Error, graph::{Graph, Map}, object::MapperObject, signal::Signal, snapshot::signal_path};
+
+/// The session file version written by [Session::to_json]. Matches the version written by webmapper.
+pub const FILE_VERSION: &str = "2.4";
+
+/// A saved set of maps.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Session {
+  pub maps: Vec<MapConfig>
+}
+
+/// A description of a single map, independent of any running devices.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapConfig {
+  /// Source signal paths, in the form `device.ordinal/signal`.
+  pub sources: Vec<String>,
+  /// Destination signal paths, in the form `device.ordinal/signal`.
+  pub destinations: Vec<String>,
+  pub expression: Option<String>,
+  pub muted: bool,
+  pub protocol: mpr_proto,
+  pub process_location: mpr_loc
+}
+
+/// An error that can occur when reading or writing a session file.
+#[derive(Debug, PartialEq)]
+pub enum SessionError {
+  /// The session file could not be read or written.
+  Io(io::ErrorKind),
+  /// The session file is not valid JSON, or is missing required fields.
+  InvalidFormat(String)
+}
+
+impl fmt::Display for SessionError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SessionError::Io(kind) => write!(f, "failed to access session file: {}", kind),
+      SessionError::InvalidFormat(msg) => write!(f, "invalid session file: {}", msg)
+    }
+  }
+}
+
+impl Session {
+  /// Capture the maps between the given devices.
+  ///
+  /// A map is included if every one of it's endpoints belongs to one of `devices`.
+  /// Device names may include an ordinal (`"synth.1"`) or omit it (`"synth"`) to match any ordinal.
+  /// If `devices` is empty, every map visible to the graph is included.
+  ///
+  /// The graph must be subscribed to maps (see [Graph::subscribe]) to see maps between remote devices.
+  pub fn capture(graph: &Graph, devices: &[&str]) -> Session {
+    let maps = graph.get_maps().iter()
+      .map(MapConfig::from_map)
+      .filter(|map| devices.is_empty() || map.sources.iter().chain(&map.destinations).all(|path| {
+        devices.iter().any(|name| device_matches(path_device(path), name))
+      }))
+      .collect();
+    Session { maps }
+  }
+
+  /// Serialize the session to a webmapper compatible JSON string.
+  pub fn to_json(&self) -> String {
+    let file = SessionFile {
+      fileversion: FILE_VERSION.to_string(),
+      mapping: Mapping {
+        maps: self.maps.iter().map(MapEntry::from).collect()
+      }
+    };
+    serde_json::to_string_pretty(&file).expect("session serialization cannot fail")
+  }
+
+  /// Parse a session from a JSON string.
+  pub fn from_json(json: &str) -> Result<Session, Error> {
+    let file: SessionFile = serde_json::from_str(json)
+      .map_err(|e| SessionError::InvalidFormat(e.to_string()))?;
+    Ok(Session {
+      maps: file.mapping.maps.into_iter().map(MapConfig::from).collect()
+    })
+  }
+
+  /// Write the session to a file as JSON.
+  pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+    fs::write(path, self.to_json()).map_err(|e| SessionError::Io(e.kind()))?;
+    Ok(())
+  }
+
+  /// Read a session from a JSON file.
+  pub fn load(path: impl AsRef<Path>) -> Result<Session, Error> {
+    let json = fs::read_to_string(path).map_err(|e| SessionError::Io(e.kind()))?;
+    Session::from_json(&json)
+  }
+
+  /// Recreate the maps in this session, waiting up to `timeout` for their devices to appear.
+  ///
+  /// The graph is subscribed to all devices and polled while waiting.
+  /// Each map is created and pushed as soon as all of it's endpoints can be found;
+  /// endpoints saved with an ordinal that no longer exists will match a device with the same name and any ordinal.
+  ///
+  /// Returns once every map has been created and is ready, or when the timeout has elapsed.
+  /// The result holds every map that was created; use [Map::is_ready] to check for maps that were pushed but didn't become ready in time.
+  /// Maps whose signals did not appear before the timeout are skipped, compare the length of the result with `self.maps` to detect this.
+  pub fn restore(&self, graph: &Graph, timeout: Duration) -> Vec<Map> {
+    graph.subscribe(None, &[mpr_type::MPR_DEV, mpr_type::MPR_SIG, mpr_type::MPR_MAP]);
+
+    let deadline = Instant::now() + timeout;
+    let mut pending: Vec<&MapConfig> = self.maps.iter().collect();
+    let mut created = Vec::new();
+    loop {
+      pending.retain(|config| {
+        match config.create(graph) {
+          Some(map) => {
+            created.push(map);
+            false
+          },
+          None => true
+        }
+      });
+      let remaining = deadline.saturating_duration_since(Instant::now());
+      // the last maps were only just pushed, keep polling until the graph has seen them become ready
+      if (pending.is_empty() && created.iter().all(Map::is_ready)) || remaining.is_zero() {
+        return created;
+      }
+      graph.poll_and_block(remaining.min(WAIT_POLL_INTERVAL));
+    }
+  }
+}
+
+impl MapConfig {
+  /// Describe an existing map.
+  pub fn from_map(map: &Map) -> MapConfig {
+    MapConfig {
+      sources: map.get_signals(mpr_loc::MPR_LOC_SRC).iter().map(signal_path).collect(),
+      destinations: map.get_signals(mpr_loc::MPR_LOC_DST).iter().map(signal_path).collect(),
+      expression: map.get_expr(),
+      muted: map.is_muted(),
+      protocol: map.get_protocol(),
+      process_location: map.get_process_location()
+    }
+  }
+
+  /// Create and push this map, if all of it's endpoints are visible to the graph.
+  fn create(&self, graph: &Graph) -> Option<Map> {
+    let sources = self.sources.iter().map(|path| find_signal(graph, path)).collect::<Option<Vec<_>>>()?;
+    let destinations = self.destinations.iter().map(|path| find_signal(graph, path)).collect::<Option<Vec<_>>>()?;
+    let map = Map::try_create_from(&sources.iter().collect::<Vec<_>>(), &destinations.iter().collect::<Vec<_>>()).ok()?;
+    if let Some(expression) = &self.expression {
+      map.try_set_expr(expression).ok()?;
+    }
+    map.set_muted(self.muted);
+    if self.protocol != mpr_proto::MPR_PROTO_UNDEFINED {
+      map.set_protocol(self.protocol);
+    }
+    if self.process_location != mpr_loc::MPR_LOC_UNDEFINED {
+      map.set_process_location(self.process_location);
+    }
+    map.push();
+    Some(map)
+  }
+}
+
+/// Find a signal by it's path, falling back to any device with the same name if the ordinal doesn't match.
+fn find_signal(graph: &Graph, path: &str) -> Option<Signal> {
+  if let Some(signal) = graph.find_signal(path) {
+    return Some(signal);
+  }
+  let (device, signal) = path.trim_start_matches('/').split_once('/')?;
+  let base = strip_ordinal(device);
+  graph.get_devices().iter()
+    .filter(|dev| dev.get_property_str(mpr_prop::MPR_PROP_NAME).is_ok_and(|name| strip_ordinal(&name) == base))
+    .find_map(|dev| dev.signal(signal))
+}
+
+/// Get the device part of a `device/signal` path.
+fn path_device(path: &str) -> &str {
+  let path = path.trim_start_matches('/');
+  path.split_once('/').map_or(path, |(device, _)| device)
+}
+
+/// Remove the `.ordinal` suffix from a device name, if present.
+fn strip_ordinal(name: &str) -> &str {
+  match name.rsplit_once('.') {
+    Some((base, ordinal)) if !ordinal.is_empty() && ordinal.bytes().all(|b| b.is_ascii_digit()) => base,
+    _ => name
+  }
+}
+
+/// Returns `true` if `device` (with ordinal) is the device described by `name` (with or without ordinal).
+fn device_matches(device: &str, name: &str) -> bool {
+  device == name || strip_ordinal(device) == name
+}
+
+/// The on-disk layout of a session, matching webmapper.
+#[derive(Serialize, Deserialize)]
+struct SessionFile {
+  fileversion: String,
+  mapping: Mapping
+}
+
+#[derive(Serialize, Deserialize)]
+struct Mapping {
+  maps: Vec<MapEntry>
+}
+
+#[derive(Serialize, Deserialize)]
+struct MapEntry {
+  sources: Vec<String>,
+  destinations: Vec<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  expression: Option<String>,
+  #[serde(default)]
+  muted: bool,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  protocol: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  process_loc: Option<String>
+}
+
+impl From<&MapConfig> for MapEntry {
+  fn from(config: &MapConfig) -> Self {
+    MapEntry {
+      sources: config.sources.clone(),
+      destinations: config.destinations.clone(),
+      expression: config.expression.clone(),
+      muted: config.muted,
+      protocol: match config.protocol {
+        mpr_proto::MPR_PROTO_UDP => Some("udp".to_string()),
+        mpr_proto::MPR_PROTO_TCP => Some("tcp".to_string()),
+        _ => None
+      },
+      process_loc: match config.process_location {
+        mpr_loc::MPR_LOC_SRC => Some("src".to_string()),
+        mpr_loc::MPR_LOC_DST => Some("dst".to_string()),
+        _ => None
+      }
+    }
+  }
+}
+
+impl From<MapEntry> for MapConfig {
+  fn from(entry: MapEntry) -> Self {
+    MapConfig {
+      sources: entry.sources,
+      destinations: entry.destinations,
+      expression: entry.expression,
+      muted: entry.muted,
+      protocol: match entry.protocol.as_deref() {
+        Some("udp") => mpr_proto::MPR_PROTO_UDP,
+        Some("tcp") => mpr_proto::MPR_PROTO_TCP,
+        _ => mpr_proto::MPR_PROTO_UNDEFINED
+      },
+      process_location: match entry.process_loc.as_deref() {
+        Some("src") => mpr_loc::MPR_LOC_SRC,
+        Some("dst") => mpr_loc::MPR_LOC_DST,
+        _ => mpr_loc::MPR_LOC_UNDEFINED
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{bindings::mpr_dir, device::Device};
+
+  /// A session saved by webmapper, including the fields libmapper-rs ignores (`scope`, `bundle`, ...).
+  const WEBMAPPER_SESSION: &str = include_str!("../tests/fixtures/webmapper_session.json");
+
+  #[test]
+  fn parses_webmapper_session() {
+    let session = Session::from_json(WEBMAPPER_SESSION).unwrap();
+    assert_eq!(session.maps, vec![
+      MapConfig {
+        sources: vec!["synth.1/freq".to_string()],
+        destinations: vec!["filter.1/cutoff".to_string()],
+        expression: Some("y=x*2+100".to_string()),
+        muted: false,
+        protocol: mpr_proto::MPR_PROTO_UDP,
+        process_location: mpr_loc::MPR_LOC_SRC
+      },
+      MapConfig {
+        sources: vec!["synth.1/amp".to_string(), "filter.1/level".to_string()],
+        destinations: vec!["lights.2/brightness".to_string()],
+        expression: Some("y=x0+x1".to_string()),
+        muted: true,
+        protocol: mpr_proto::MPR_PROTO_TCP,
+        process_location: mpr_loc::MPR_LOC_DST
+      }
+    ]);
+  }
+
+  #[test]
+  fn json_round_trip() {
+    let mut session = Session::from_json(WEBMAPPER_SESSION).unwrap();
+    session.maps.push(MapConfig {
+      sources: vec!["a.1/out".to_string()],
+      destinations: vec!["b.1/in".to_string()],
+      expression: None,
+      muted: false,
+      protocol: mpr_proto::MPR_PROTO_UNDEFINED,
+      process_location: mpr_loc::MPR_LOC_UNDEFINED
+    });
+    let json = session.to_json();
+    assert!(json.contains(&format!("\"fileversion\": \"{}\"", FILE_VERSION)));
+    assert_eq!(Session::from_json(&json).unwrap(), session);
+  }
+
+  #[test]
+  fn restore_waits_for_pushed_maps() {
+    let graph = Graph::create();
+    let src = Device::create_from_graph("rs_session_src", &graph);
+    let dst = Device::create_from_graph("rs_session_dst", &graph);
+    let _out = src.create_signal::<f32>("out", mpr_dir::MPR_DIR_OUT);
+    let _in = dst.create_signal::<f32>("in", mpr_dir::MPR_DIR_IN);
+    src.poll();
+    dst.poll();
+
+    let session = Session {
+      maps: vec![MapConfig {
+        sources: vec!["rs_session_src.1/out".to_string()],
+        destinations: vec!["rs_session_dst.1/in".to_string()],
+        expression: Some("y=x*2".to_string()),
+        muted: false,
+        protocol: mpr_proto::MPR_PROTO_UNDEFINED,
+        process_location: mpr_loc::MPR_LOC_UNDEFINED
+      }]
+    };
+    let maps = session.restore(&graph, Duration::from_secs(5));
+    assert_eq!(maps.len(), 1);
+    assert!(maps[0].is_ready());
+  }
+
+  #[test]
+  fn rejects_invalid_json() {
+    assert!(matches!(Session::from_json("{\"maps\": []}"), Err(Error::Session(SessionError::InvalidFormat(_)))));
+  }
+}
@@ -1,20 +1,35 @@
 
-use std::ffi::c_void;
+//! Signals and their instances.
+//! 
+//! A [Signal] is created by a [Device](crate::device::Device) and can be connected to other signals with a [Map].
+//! Signals with more than one instance can be configured with [Signal::reserve_instances], [Signal::set_steal_mode]
+//! and [Signal::on_instance_overflow].
+//! 
+//! Signal groups (`mpr_sig_group`) are not supported, the libmapper 2.4.9 bindings this crate is built against don't expose them.
+use std::{ffi::{c_int, c_void}, ptr};
 
-use crate::{bindings::{mpr_dir, mpr_prop, mpr_sig, mpr_sig_free, mpr_sig_get_inst_status, mpr_sig_get_value, mpr_sig_set_value, mpr_status, mpr_type}, device::MappableType, object::MapperObject};
+use crate::{backend::{mpr_dev_set_time, mpr_dir, mpr_id, mpr_obj_get_prop_as_ptr, mpr_obj_set_prop, mpr_prop, mpr_sig, mpr_sig_evt, mpr_sig_free, mpr_sig_get_dev, mpr_sig_get_inst_status, mpr_sig_get_maps, mpr_sig_get_value, mpr_sig_reserve_inst, mpr_sig_set_cb, mpr_sig_set_value, mpr_status, mpr_steal_type, mpr_time, mpr_type}, device::{MappableType, SignalType}, graph::Map, object::{MapperObject, PropertyError}, time::Timetag};
 
 pub struct Signal {
     pub(crate) handle: mpr_sig,
     pub(crate) owned: bool,
     pub(crate) data_type: mpr_type,
-    pub(crate) vector_length: u32
+    pub(crate) vector_length: u32,
+    pub(crate) overflow_handler: Option<Box<OverflowHandler>>
 }
 
+/// A handler called with the requested instance id when a signal runs out of instances.
+type OverflowHandler = Box<dyn FnMut(mpr_id) + Send>;
+
 unsafe impl Send for Signal {}
 unsafe impl Sync for Signal {}
 
 impl Drop for Signal {
     fn drop(&mut self) {
+        if self.overflow_handler.is_some() {
+            // make sure libmapper can't call into the handler after it's freed
+            self.clear_overflow_handler();
+        }
         if self.owned {
             unsafe {
                 mpr_sig_free(self.handle);
@@ -23,8 +38,31 @@ impl Drop for Signal {
     }
 }
 
+impl Signal {
+    /// Record a local update at `TRACE` level, if it is sampled (see [set_update_sampling](crate::trace::set_update_sampling)).
+    fn trace_update(&self) {
+        #[cfg(feature = "tracing")]
+        if crate::trace::sample_update() {
+            tracing::trace!(signal = %crate::snapshot::signal_path(self), length = self.vector_length, "signal updated");
+        }
+    }
+
+    /// Wrap a signal owned by libmapper (e.g. one returned from a list), reading it's type and length.
+    pub(crate) fn try_from_unowned(handle: mpr_sig) -> Result<Signal, PropertyError> {
+        let data_type = handle.get_property::<mpr_type>(mpr_prop::MPR_PROP_TYPE)?;
+        let vector_length = handle.get_property::<i32>(mpr_prop::MPR_PROP_LEN)? as u32;
+        Ok(Signal {
+            handle,
+            data_type,
+            owned: false,
+            vector_length,
+            overflow_handler: None
+        })
+    }
+}
+
 /// An error that can occur when getting or setting the value of a signal.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum SignalError {
     #[doc = "The data type of the signal does not match the type of the passed generic type."]
     WrongType,
@@ -34,6 +72,16 @@ pub enum SignalError {
     WrongLengthArg
 }
 
+impl std::fmt::Display for SignalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignalError::WrongType => write!(f, "value type does not match the signal's type"),
+            SignalError::NoValue => write!(f, "signal has no value"),
+            SignalError::WrongLengthArg => write!(f, "value length does not match the signal's vector length")
+        }
+    }
+}
+
 impl Signal {
     /// Get the status of the signal instance.
     /// Calling this function will reset the flags `was_set_remote` and `was_set_local` and return their pre-reset values.
@@ -49,10 +97,10 @@ impl Signal {
     ///        dev.poll_and_block(Duration::from_millis(10));
     /// 
     ///        if sig.get_status().was_set_remote() { // check if there's a new value waiting for us
-    ///          let (new_value, _) = sig.get_value_single::<f64>().unwrap();
+    ///          let (new_value, _) = sig.get_value_scalar::<f64>().unwrap();
     ///          *value = new_value;
     ///        } else {
-    ///          sig.set_value_single(value);
+    ///          sig.set_value_scalar(value).unwrap();
     ///        }
     ///     }
     /// }
@@ -77,6 +125,156 @@ impl Signal {
     }
 }
 
+/// What a signal should do when a new instance is needed but all instances are in use.
+/// 
+/// Wraps libmapper's `mpr_steal_type`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum StealMode {
+    /// No stealing will take place, new instances are dropped (see [Signal::on_instance_overflow]).
+    None,
+    /// Release the oldest active instance and reuse it.
+    Oldest,
+    /// Release the newest active instance and reuse it.
+    Newest
+}
+
+impl From<StealMode> for mpr_steal_type {
+    fn from(mode: StealMode) -> Self {
+        match mode {
+            StealMode::None => mpr_steal_type::MPR_STEAL_NONE,
+            StealMode::Oldest => mpr_steal_type::MPR_STEAL_OLDEST,
+            StealMode::Newest => mpr_steal_type::MPR_STEAL_NEWEST
+        }
+    }
+}
+
+impl From<mpr_steal_type> for StealMode {
+    fn from(mode: mpr_steal_type) -> Self {
+        match mode {
+            mpr_steal_type::MPR_STEAL_NONE => StealMode::None,
+            mpr_steal_type::MPR_STEAL_OLDEST => StealMode::Oldest,
+            mpr_steal_type::MPR_STEAL_NEWEST => StealMode::Newest
+        }
+    }
+}
+
+impl Signal {
+    /// Reserve `count` additional instances for this signal.
+    /// Returns the number of instances that were actually reserved.
+    pub fn reserve_instances(&self, count: u32) -> u32 {
+        unsafe {
+            mpr_sig_reserve_inst(self.handle, count as c_int, ptr::null_mut(), ptr::null_mut()).max(0) as u32
+        }
+    }
+
+    /// Set what happens when a new instance is needed but all of this signal's instances are in use.
+    /// 
+    /// # Examples
+    /// Limit a synth to 8 voices, reusing the oldest voice when a ninth note arrives:
+    /// ```
+    /// use libmapper_rs::device::Device;
+    /// use libmapper_rs::signal::StealMode;
+    /// use libmapper_rs::constants::mpr_dir;
+    /// fn setup_voices(dev: &Device) {
+    ///     let freq = dev.create_signal::<f32>("freq", mpr_dir::MPR_DIR_IN);
+    ///     freq.reserve_instances(7);
+    ///     freq.set_steal_mode(StealMode::Oldest);
+    /// }
+    /// ```
+    pub fn set_steal_mode(&self, mode: StealMode) {
+        self.set_property(mpr_prop::MPR_PROP_STEAL_MODE, mpr_steal_type::from(mode) as i32);
+    }
+
+    /// Get the instance stealing mode of this signal.
+    pub fn get_steal_mode(&self) -> Result<StealMode, PropertyError> {
+        let mode = self.get_property::<i32>(mpr_prop::MPR_PROP_STEAL_MODE)?;
+        let mode = [mpr_steal_type::MPR_STEAL_OLDEST, mpr_steal_type::MPR_STEAL_NEWEST].into_iter()
+            .find(|m| *m as i32 == mode)
+            .unwrap_or(mpr_steal_type::MPR_STEAL_NONE);
+        Ok(mode.into())
+    }
+
+    /// Register a handler that is called whenever an instance is requested but none are available (`MPR_SIG_INST_OFLW`).
+    /// The handler is passed the id of the instance that could not be allocated, and is called from whichever thread polls the device.
+    /// 
+    /// This replaces any previously registered overflow handler.
+    /// 
+    /// The handler is installed as the signal's libmapper callback and stored in its `MPR_PROP_DATA` property,
+    /// so it can't be combined with anything else that uses them, such as a [Recorder](crate::record::Recorder) tapping this signal.
+    /// Whichever is installed last wins.
+    /// 
+    /// The handler is owned by this `Signal` value and removed when it is dropped. This includes the unowned
+    /// signals returned by [Device::signal](crate::device::Device::signal), so keep that value alive for as long as the handler is needed.
+    pub fn on_instance_overflow<F: FnMut(u64) + Send + 'static>(&mut self, handler: F) {
+        let handler: Box<OverflowHandler> = Box::new(Box::new(handler));
+        let data = &*handler as *const OverflowHandler as *const c_void;
+        unsafe {
+            mpr_obj_set_prop(self.handle, mpr_prop::MPR_PROP_DATA, ptr::null(), 1, mpr_type::MPR_PTR, data, 0);
+            mpr_sig_set_cb(self.handle, Some(overflow_trampoline), mpr_sig_evt::MPR_SIG_INST_OFLW as c_int);
+        }
+        self.overflow_handler = Some(handler);
+    }
+
+    /// Remove the handler registered with [on_instance_overflow](Signal::on_instance_overflow).
+    pub fn clear_overflow_handler(&mut self) {
+        unsafe {
+            mpr_sig_set_cb(self.handle, None, 0);
+            mpr_obj_set_prop(self.handle, mpr_prop::MPR_PROP_DATA, ptr::null(), 1, mpr_type::MPR_PTR, ptr::null(), 0);
+        }
+        self.overflow_handler = None;
+    }
+}
+
+/// Forwards `MPR_SIG_INST_OFLW` events from libmapper to the handler stored in the signal's `MPR_PROP_DATA`.
+unsafe extern "C" fn overflow_trampoline(signal: mpr_sig, event: mpr_sig_evt, instance: mpr_id, _length: c_int,
+    _type: mpr_type, _value: *const c_void, _time: mpr_time) {
+    if event != mpr_sig_evt::MPR_SIG_INST_OFLW {
+        return;
+    }
+    let data = mpr_obj_get_prop_as_ptr(signal, mpr_prop::MPR_PROP_DATA, ptr::null()) as *mut OverflowHandler;
+    if let Some(handler) = data.as_mut() {
+        handler(instance);
+    }
+}
+
+/// A set of signals described by the fields of a plain data struct.
+/// 
+/// This is usually implemented with `#[derive(MapperSignals)]` (requires the `derive` feature),
+/// which creates a companion struct holding one [Signal] per annotated field:
+/// 
+/// ```text
+/// #[derive(MapperSignals)]
+/// struct Synth {
+///     #[signal(dir = "in", unit = "Hz", min = 20.0, max = 20000.0)]
+///     freq: f32,
+///     #[signal(dir = "out", name = "env/position")]
+///     position: [f32; 3],
+/// }
+/// 
+/// let mut signals = Synth::create_signals(&dev)?; // a `SynthSignals` struct
+/// synth.read_all(&signals);
+/// synth.write_all(&mut signals)?;
+/// ```
+/// 
+/// Field types must implement [SignalType](crate::device::SignalType). Fields without a `#[signal]` attribute are ignored.
+pub trait MapperSignals: Sized {
+    /// The struct holding one [Signal] per field.
+    type Signals;
+
+    /// Create every signal on the given device, applying their units and ranges.
+    fn create_signals(device: &crate::device::Device) -> Result<Self::Signals, crate::error::Error>;
+
+    /// Copy the current values of the input signals into this struct.
+    /// Fields whose signal has no value yet are left unchanged.
+    fn read_all(&mut self, signals: &Self::Signals);
+
+    /// Set the values of the output signals from this struct.
+    fn write_all(&self, signals: &mut Self::Signals) -> Result<(), crate::error::Error>;
+}
+
+#[cfg(feature = "derive")]
+pub use libmapper_rs_derive::MapperSignals;
+
 /// A struct that represents the status of a signal instance.
 /// When this struct is created by Signal::get_status(), the flags `was_set_remote` and `was_set_local` will be reset.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -117,6 +315,7 @@ impl Signal {
         unsafe {
             mpr_sig_set_value(self.handle, 0, 1,  self.data_type, value as *const T as *const c_void);
         }
+        self.trace_update();
         Ok(())
     }
 
@@ -124,7 +323,8 @@ impl Signal {
     /// This function will return [`SignalError::WrongType`](SignalError:WrongType) if the passed generic type doesn't match the signal's type.
     /// 
     /// If this signal is a vector, only the first element of the vector will be returned.
-    pub fn get_value_scalar<T: MappableType + Copy>(&self) -> Result<(T, u64), SignalError> {
+    /// The returned [Timetag] is the time at which the value was sampled.
+    pub fn get_value_scalar<T: MappableType + Copy>(&self) -> Result<(T, Timetag), SignalError> {
         let mut time = 0;
         if T::get_mpr_type() != self.data_type {
             return Err(SignalError::WrongType);
@@ -135,7 +335,7 @@ impl Signal {
                 return Err(SignalError::NoValue);
             }
             let value = *(ptr as *const T);
-            Ok((value, time))
+            Ok((value, Timetag(time)))
         }
     }
 
@@ -143,7 +343,8 @@ impl Signal {
     /// This function will return [`SignalError::WrongType`](SignalError:WrongType) if the passed generic type doesn't match the signal's type.
     /// 
     /// The length of the returned slice will be equal to the value returned by [get_vector_length](Signal::get_vector_length).
-    pub fn get_value<T: MappableType + Copy>(&self) -> Result<(Vec<T>, u64), SignalError> {
+    /// The returned [Timetag] is the time at which the value was sampled.
+    pub fn get_value<T: MappableType + Copy>(&self) -> Result<(Vec<T>, Timetag), SignalError> {
         let mut time = 0;
         if T::get_mpr_type() != self.data_type {
             return Err(SignalError::WrongType);
@@ -154,7 +355,7 @@ impl Signal {
                 return Err(SignalError::NoValue);
             }
             let slice = std::slice::from_raw_parts(ptr as *const T, self.vector_length as usize);
-            Ok((slice.to_vec(), time))
+            Ok((slice.to_vec(), Timetag(time)))
         }
     }
 
@@ -173,21 +374,146 @@ impl Signal {
         unsafe {
             mpr_sig_set_value(self.handle, 0, self.vector_length as i32, self.data_type, values.as_ptr() as *const c_void);
         }
+        self.trace_update();
+        Ok(())
+    }
+
+    /// Set the minimum and maximum values of the signal.
+    /// This function will return [`SignalError::WrongType`](SignalError:WrongType) if the passed generic type doesn't match the signal's type.
+    /// 
+    /// The length of both slices must be equal to the value returned by [get_vector_length](Signal::get_vector_length),
+    /// otherwise this function will return `SignalError::WrongLengthArg`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use libmapper_rs::device::Device;
+    /// use libmapper_rs::constants::mpr_dir;
+    /// fn setup_signals(dev: &Device) {
+    ///     let sig = dev.create_vector_signal::<f32>("position", mpr_dir::MPR_DIR_OUT, 3);
+    ///     sig.set_range(&[-1.0, -1.0, 0.0], &[1.0, 1.0, 10.0]).unwrap();
+    /// }
+    /// ```
+    pub fn set_range<T: MappableType + Copy>(&self, min: &[T], max: &[T]) -> Result<(), SignalError> {
+        if T::get_mpr_type() != self.data_type {
+            return Err(SignalError::WrongType);
+        }
+        if min.len() != self.vector_length as usize || max.len() != self.vector_length as usize {
+            return Err(SignalError::WrongLengthArg);
+        }
+        self.set_property_vec(mpr_prop::MPR_PROP_MIN, min);
+        self.set_property_vec(mpr_prop::MPR_PROP_MAX, max);
+        Ok(())
+    }
+
+    /// Set the value of the signal, sending it with the specified timetag instead of the device's current time.
+    /// This function will return [`SignalError::WrongType`](SignalError:WrongType) if the passed generic type doesn't match the signal's type.
+    /// 
+    /// The length of the slice must be equal to the value returned by [get_vector_length](Signal::get_vector_length).
+    /// If the lengths are not equal this function return an `Err` of `SignalError::WrongLengthArg`.
+    /// 
+    /// # Notes
+    /// This sets the time of the parent device (see [Device::set_time](crate::device::Device::set_time)),
+    /// so other updates made by the same device before it is next polled will share the timetag.
+    pub fn set_value_at<T: MappableType + Copy>(&mut self, values: &[T], time: Timetag) -> Result<(), SignalError> {
+        if T::get_mpr_type() != self.data_type {
+            return Err(SignalError::WrongType);
+        }
+        if values.len() != self.vector_length as usize {
+            return Err(SignalError::WrongLengthArg);
+        }
+        unsafe {
+            mpr_dev_set_time(mpr_sig_get_dev(self.handle), time.0);
+            mpr_sig_set_value(self.handle, 0, self.vector_length as i32, self.data_type, values.as_ptr() as *const c_void);
+        }
+        self.trace_update();
         Ok(())
     }
 
+    /// Get the complete value of the signal as a [SignalType], such as a fixed-length array.
+    /// This function will return [`SignalError::WrongType`](SignalError:WrongType) if the element type doesn't match the signal's type,
+    /// and `SignalError::WrongLengthArg` if the length of `T` doesn't match the signal's vector length.
+    /// 
+    /// Unlike [get_value](Signal::get_value), this does not allocate.
+    /// 
+    /// # Examples
+    /// ```
+    /// use libmapper_rs::device::Device;
+    /// use libmapper_rs::constants::mpr_dir;
+    /// fn read_position(dev: &Device) -> [f32; 3] {
+    ///     let sig = dev.create_signal::<[f32; 3]>("position", mpr_dir::MPR_DIR_IN);
+    ///     sig.get_value_as::<[f32; 3]>().map(|(v, _)| v).unwrap_or([0.0; 3])
+    /// }
+    /// ```
+    pub fn get_value_as<T: SignalType>(&self) -> Result<(T, Timetag), SignalError> {
+        let mut time = 0;
+        if T::Element::get_mpr_type() != self.data_type {
+            return Err(SignalError::WrongType);
+        }
+        if T::LENGTH != self.vector_length {
+            return Err(SignalError::WrongLengthArg);
+        }
+        unsafe {
+            let ptr = mpr_sig_get_value(self.handle, 0, &mut time);
+            if ptr.is_null() {
+                return Err(SignalError::NoValue);
+            }
+            Ok((ptr::read_unaligned(ptr as *const T), Timetag(time)))
+        }
+    }
+
+    /// Set the complete value of the signal from a [SignalType], such as a fixed-length array.
+    /// This function will return [`SignalError::WrongType`](SignalError:WrongType) if the element type doesn't match the signal's type,
+    /// and `SignalError::WrongLengthArg` if the length of `T` doesn't match the signal's vector length.
+    pub fn set_value_as<T: SignalType>(&mut self, value: &T) -> Result<(), SignalError> {
+        if T::Element::get_mpr_type() != self.data_type {
+            return Err(SignalError::WrongType);
+        }
+        if T::LENGTH != self.vector_length {
+            return Err(SignalError::WrongLengthArg);
+        }
+        unsafe {
+            mpr_sig_set_value(self.handle, 0, self.vector_length as i32, self.data_type, value as *const T as *const c_void);
+        }
+        self.trace_update();
+        Ok(())
+    }
+
+    /// Get a list of all maps connected to this signal.
+    /// 
+    /// `direction` selects incoming (`MPR_DIR_IN`), outgoing (`MPR_DIR_OUT`) or all (`MPR_DIR_ANY`) maps.
+    pub fn get_maps(&self, direction: mpr_dir) -> Vec<Map> {
+        let list = unsafe { mpr_sig_get_maps(self.handle, direction) };
+        crate::util::read_list(list, |ptr| Map {
+            handle: ptr,
+            owned: false
+        })
+    }
+
+    /// Get the name of the device that owns this signal.
+    pub fn get_device_name(&self) -> Result<String, PropertyError> {
+        let device = unsafe { mpr_sig_get_dev(self.handle) };
+        device.get_property_str(mpr_prop::MPR_PROP_NAME)
+    }
+
     /// Get the direction of the signal. This value determines how signal data can flow to/from this signal.
     /// 
     /// For example, you cannot map to a signal with direction `MPR_DIR_OUT`.
+    /// 
+    /// If the direction can't be read, this will return `MPR_DIR_UNDEFINED`. Use [try_get_direction](Signal::try_get_direction) to detect this.
     pub fn get_direction(&self) -> mpr_dir {
-        let val = self.get_property::<i32>(mpr_prop::MPR_PROP_DIR).unwrap();
-        match val {
+        self.try_get_direction().unwrap_or(mpr_dir::MPR_DIR_UNDEFINED)
+    }
+
+    /// Get the direction of the signal, returning an error if the property can't be read.
+    pub fn try_get_direction(&self) -> Result<mpr_dir, PropertyError> {
+        let val = self.get_property::<i32>(mpr_prop::MPR_PROP_DIR)?;
+        Ok(match val {
             0 => mpr_dir::MPR_DIR_UNDEFINED,
             1 => mpr_dir::MPR_DIR_IN,
             2 => mpr_dir::MPR_DIR_OUT,
             3 => mpr_dir::MPR_DIR_ANY,
             7 => mpr_dir::MPR_DIR_BOTH,
             _ => mpr_dir::MPR_DIR_UNDEFINED
-        }
+        })
     }
 }
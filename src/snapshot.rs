@@ -0,0 +1,278 @@
+//! Point-in-time copies of the distributed graph.
+//!
+//! A [GraphSnapshot] holds plain rust values (no libmapper handles), so it can be logged, diffed, or
+//! serialized with serde when the `serde` feature is enabled.
+use std::collections::BTreeMap;
+
+use crate::{bindings::{mpr_dir, mpr_loc, mpr_prop, mpr_type}, device::Device, graph::{Graph, Map}, object::{MapperId, MapperObject, PropertyValue}, signal::Signal};
+
+/// A copy of every device, signal and map visible to a [Graph].
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use libmapper_rs::graph::Graph;
+/// use libmapper_rs::snapshot::GraphSnapshot;
+/// use libmapper_rs::constants::mpr_type;
+/// let graph = Graph::create();
+/// graph.subscribe(None, &[mpr_type::MPR_OBJ]);
+/// graph.poll_and_block(Duration::from_millis(500));
+/// let snapshot = GraphSnapshot::capture(&graph);
+/// for device in &snapshot.devices {
+///   println!("{} has {} signals", device.name, device.signals.len());
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraphSnapshot {
+  pub devices: Vec<DeviceSnapshot>,
+  pub maps: Vec<MapSnapshot>
+}
+
+/// A copy of a device and it's signals.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceSnapshot {
+  /// The device's name, including it's ordinal (e.g. `"synth.1"`).
+  pub name: String,
+  pub id: MapperId,
+  pub signals: Vec<SignalSnapshot>,
+  pub properties: BTreeMap<String, PropertyValue>
+}
+
+/// A copy of a signal.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SignalSnapshot {
+  pub name: String,
+  pub id: MapperId,
+  pub direction: SignalDirection,
+  /// The signal's data type as a type character (`'f'`, `'d'`, `'i'`, ...).
+  pub data_type: char,
+  pub vector_length: u32,
+  pub properties: BTreeMap<String, PropertyValue>
+}
+
+/// The direction of a signal in a snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SignalDirection {
+  In,
+  Out,
+  Undefined
+}
+
+impl From<mpr_dir> for SignalDirection {
+  fn from(direction: mpr_dir) -> Self {
+    match direction {
+      mpr_dir::MPR_DIR_IN => SignalDirection::In,
+      mpr_dir::MPR_DIR_OUT => SignalDirection::Out,
+      _ => SignalDirection::Undefined
+    }
+  }
+}
+
+/// A copy of a map. Endpoints are identified by their full `device/signal` path.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MapSnapshot {
+  pub id: MapperId,
+  pub sources: Vec<String>,
+  pub destinations: Vec<String>,
+  pub expression: Option<String>,
+  pub muted: bool,
+  pub properties: BTreeMap<String, PropertyValue>
+}
+
+impl GraphSnapshot {
+  /// Copy the current state of the graph.
+  ///
+  /// Only objects the graph is subscribed to (see [Graph::subscribe]) or owns will be included.
+  pub fn capture(graph: &Graph) -> GraphSnapshot {
+    GraphSnapshot {
+      devices: graph.get_devices().iter().map(DeviceSnapshot::capture).collect(),
+      maps: graph.get_maps().iter().map(MapSnapshot::capture).collect()
+    }
+  }
+
+  /// Find a device in the snapshot by it's name.
+  pub fn device(&self, name: &str) -> Option<&DeviceSnapshot> {
+    self.devices.iter().find(|d| d.name == name)
+  }
+
+  /// Render the mapping topology as a [Graphviz](https://graphviz.org) DOT graph.
+  ///
+  /// Each device is drawn as a cluster containing it's signals, with inputs in blue and outputs in red.
+  /// Maps are drawn as edges from every source to every destination, labelled with their expression.
+  /// Muted maps are drawn dashed.
+  ///
+  /// # Examples
+  /// ```
+  /// use libmapper_rs::snapshot::GraphSnapshot;
+  /// let dot = GraphSnapshot::default().to_dot();
+  /// assert!(dot.starts_with("digraph libmapper {"));
+  /// ```
+  pub fn to_dot(&self) -> String {
+    let mut dot = String::from("digraph libmapper {\n  rankdir=LR;\n  node [shape=box, style=filled];\n");
+    for (i, device) in self.devices.iter().enumerate() {
+      dot.push_str(&format!("  subgraph cluster_{} {{\n    label={};\n", i, dot_quote(&device.name)));
+      for signal in &device.signals {
+        let color = match signal.direction {
+          SignalDirection::In => "lightblue",
+          SignalDirection::Out => "salmon",
+          SignalDirection::Undefined => "lightgrey"
+        };
+        dot.push_str(&format!("    {} [label={}, fillcolor={}];\n",
+          dot_quote(&format!("{}/{}", device.name, signal.name)), dot_quote(&signal.name), color));
+      }
+      dot.push_str("  }\n");
+    }
+    for map in &self.maps {
+      let mut attributes = vec![format!("label={}", dot_quote(map.expression.as_deref().unwrap_or("")))];
+      if map.muted {
+        attributes.push("style=dashed".to_string());
+      }
+      let attributes = attributes.join(", ");
+      for source in &map.sources {
+        for destination in &map.destinations {
+          dot.push_str(&format!("  {} -> {} [{}];\n", dot_quote(source), dot_quote(destination), attributes));
+        }
+      }
+    }
+    dot.push_str("}\n");
+    dot
+  }
+}
+
+impl DeviceSnapshot {
+  /// Copy the current state of a device and it's signals.
+  pub fn capture(device: &Device) -> DeviceSnapshot {
+    DeviceSnapshot {
+      name: device.get_property_str(mpr_prop::MPR_PROP_NAME).unwrap_or_default(),
+      id: device.id(),
+      signals: device.get_signals(mpr_dir::MPR_DIR_ANY).iter().map(SignalSnapshot::capture).collect(),
+      properties: device.get_properties().into_iter().collect()
+    }
+  }
+}
+
+impl SignalSnapshot {
+  /// Copy the current state of a signal.
+  pub fn capture(signal: &Signal) -> SignalSnapshot {
+    SignalSnapshot {
+      name: signal.get_property_str(mpr_prop::MPR_PROP_NAME).unwrap_or_default(),
+      id: signal.id(),
+      direction: signal.get_direction().into(),
+      data_type: type_char(signal.get_data_type()),
+      vector_length: signal.get_vector_length(),
+      properties: signal.get_properties().into_iter().collect()
+    }
+  }
+}
+
+impl MapSnapshot {
+  /// Copy the current state of a map.
+  pub fn capture(map: &Map) -> MapSnapshot {
+    MapSnapshot {
+      id: map.id(),
+      sources: map.get_signals(mpr_loc::MPR_LOC_SRC).iter().map(signal_path).collect(),
+      destinations: map.get_signals(mpr_loc::MPR_LOC_DST).iter().map(signal_path).collect(),
+      expression: map.get_expr(),
+      muted: map.is_muted(),
+      properties: map.get_properties().into_iter().collect()
+    }
+  }
+}
+
+/// Get the full `device/signal` path of a signal.
+pub(crate) fn signal_path(signal: &Signal) -> String {
+  format!("{}/{}",
+    signal.get_device_name().unwrap_or_default(),
+    signal.get_property_str(mpr_prop::MPR_PROP_NAME).unwrap_or_default())
+}
+
+/// Quote a string as a DOT identifier.
+fn dot_quote(s: &str) -> String {
+  format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+/// Get the type character libmapper uses to represent a data type.
+fn type_char(data_type: mpr_type) -> char {
+  char::from_u32(data_type as u32).unwrap_or('?')
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn signal(name: &str, direction: SignalDirection) -> SignalSnapshot {
+    SignalSnapshot {
+      name: name.to_string(),
+      id: MapperId::from(0),
+      direction,
+      data_type: 'f',
+      vector_length: 1,
+      properties: BTreeMap::new()
+    }
+  }
+
+  fn map(source: &str, destination: &str, expression: Option<&str>, muted: bool) -> MapSnapshot {
+    MapSnapshot {
+      id: MapperId::from(0),
+      sources: vec![source.to_string()],
+      destinations: vec![destination.to_string()],
+      expression: expression.map(str::to_string),
+      muted,
+      properties: BTreeMap::new()
+    }
+  }
+
+  #[test]
+  fn dot_export() {
+    let snapshot = GraphSnapshot {
+      devices: vec![
+        DeviceSnapshot {
+          name: "synth.1".to_string(),
+          id: MapperId::from(1),
+          signals: vec![signal("freq", SignalDirection::Out), signal("gate", SignalDirection::In)],
+          properties: BTreeMap::new()
+        },
+        DeviceSnapshot {
+          name: "my \"filter\".1".to_string(),
+          id: MapperId::from(2),
+          signals: vec![signal("cutoff", SignalDirection::In), signal("other", SignalDirection::Undefined)],
+          properties: BTreeMap::new()
+        }
+      ],
+      maps: vec![
+        map("synth.1/freq", "my \"filter\".1/cutoff", Some("y=x*2"), false),
+        map("synth.1/freq", "synth.1/gate", None, true)
+      ]
+    };
+    let dot = snapshot.to_dot();
+    let lines: Vec<&str> = dot.lines().collect();
+    assert_eq!(lines, vec![
+      "digraph libmapper {",
+      "  rankdir=LR;",
+      "  node [shape=box, style=filled];",
+      "  subgraph cluster_0 {",
+      "    label=\"synth.1\";",
+      "    \"synth.1/freq\" [label=\"freq\", fillcolor=salmon];",
+      "    \"synth.1/gate\" [label=\"gate\", fillcolor=lightblue];",
+      "  }",
+      "  subgraph cluster_1 {",
+      "    label=\"my \\\"filter\\\".1\";",
+      "    \"my \\\"filter\\\".1/cutoff\" [label=\"cutoff\", fillcolor=lightblue];",
+      "    \"my \\\"filter\\\".1/other\" [label=\"other\", fillcolor=lightgrey];",
+      "  }",
+      "  \"synth.1/freq\" -> \"my \\\"filter\\\".1/cutoff\" [label=\"y=x*2\"];",
+      "  \"synth.1/freq\" -> \"synth.1/gate\" [label=\"\", style=dashed];",
+      "}"
+    ]);
+  }
+
+  #[test]
+  fn dot_quote_escapes() {
+    assert_eq!(dot_quote("a\\b\"c\nd"), "\"a\\\\b\\\"c\\nd\"");
+  }
+}
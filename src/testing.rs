@@ -0,0 +1,187 @@
+//! Helpers for integration tests that run several devices in one process.
+//!
+//! A [TestNetwork] creates a set of devices on a shared [Graph], restricted to the loopback interface, and waits for them to become ready.
+//! It can then connect signals and pump every device at once, and [assert_propagates] checks that a value
+//! set on an output signal arrives at an input signal.
+//!
+//! Combine this module with the `fake-backend` feature to run tests without libmapper installed or a network available.
+//!
+//! This module requires the `testing` feature.
+//!
+//! # Examples
+//! ```
+//! use std::time::Duration;
+//! use libmapper_rs::constants::mpr_dir;
+//! use libmapper_rs::graph::Graph;
+//! use libmapper_rs::testing::{assert_propagates, TestNetwork};
+//! # fn main() -> Result<(), libmapper_rs::Error> {
+//! let graph = Graph::create();
+//! let network = TestNetwork::spawn(&graph, &["synth", "filter"], Duration::from_secs(5))?;
+//! let mut freq = network.device(0).create_signal::<f32>("freq", mpr_dir::MPR_DIR_OUT);
+//! let cutoff = network.device(1).create_signal::<f32>("cutoff", mpr_dir::MPR_DIR_IN);
+//!
+//! let _map = network.connect(&freq, &cutoff, Duration::from_secs(5))?;
+//! assert_propagates(&mut freq, &cutoff, 440.0f32, Duration::from_secs(1));
+//! # Ok(())
+//! # }
+//! ```
+use std::{ffi::{c_int, CStr}, fmt::Debug, time::{Duration, Instant}};
+
+use crate::{backend::{mpr_dev, mpr_dev_poll, mpr_graph_set_interface, mpr_sig_get_dev}, device::{poll_share, Device, MappableType, WAIT_POLL_INTERVAL}, error::Error, graph::{Graph, Map}, signal::Signal, snapshot::signal_path};
+
+/// The name of the loopback network interface.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const LOOPBACK_INTERFACE: &CStr = c"lo";
+#[cfg(windows)]
+const LOOPBACK_INTERFACE: &CStr = c"Loopback Pseudo-Interface 1";
+#[cfg(not(any(target_os = "linux", target_os = "android", windows)))]
+const LOOPBACK_INTERFACE: &CStr = c"lo0";
+
+/// A set of devices sharing a graph, for testing maps between them.
+pub struct TestNetwork<'a> {
+    graph: &'a Graph,
+    devices: Vec<Device<'a>>
+}
+
+impl<'a> TestNetwork<'a> {
+    /// Create one device per name on `graph` and wait until all of them are ready.
+    ///
+    /// `graph` is switched to the loopback interface first, so the test devices can't see or be seen by other libmapper peers on the network.
+    /// Returns [`Error::CreationFailed`](Error::CreationFailed) if the loopback interface can't be used,
+    /// or [`Error::Timeout`](Error::Timeout) if the devices are not ready within `timeout`.
+    pub fn spawn(graph: &'a Graph, names: &[&str], timeout: Duration) -> Result<TestNetwork<'a>, Error> {
+        if unsafe { mpr_graph_set_interface(graph.handle, LOOPBACK_INTERFACE.as_ptr()) } != 0 {
+            return Err(Error::CreationFailed);
+        }
+        let devices = names.iter()
+            .map(|name| Device::try_create_from_graph(name, graph))
+            .collect::<Result<Vec<_>, _>>()?;
+        let network = TestNetwork {
+            graph,
+            devices
+        };
+        network.wait_until_ready(timeout)?;
+        Ok(network)
+    }
+
+    /// Get the shared graph.
+    pub fn graph(&self) -> &'a Graph {
+        self.graph
+    }
+
+    /// Get every device, in the order their names were passed to [spawn](TestNetwork::spawn).
+    pub fn devices(&self) -> &[Device<'a>] {
+        &self.devices
+    }
+
+    /// Get the device at `index`.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    pub fn device(&self, index: usize) -> &Device<'a> {
+        &self.devices[index]
+    }
+
+    /// Poll every device, spending roughly `time` in total (but at least 1ms per device).
+    pub fn pump(&self, time: Duration) {
+        let share = poll_share(time, self.devices.len());
+        for device in &self.devices {
+            device.poll_and_block(share);
+        }
+        self.graph.poll();
+    }
+
+    /// Pump every device until `condition` returns `true`, or until `timeout` has elapsed.
+    ///
+    /// Returns the last result of `condition`.
+    pub fn wait_for(&self, timeout: Duration, mut condition: impl FnMut() -> bool) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if condition() {
+                return true;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return false;
+            }
+            self.pump(remaining.min(WAIT_POLL_INTERVAL));
+        }
+    }
+
+    /// Pump every device until all of them are ready, or until `timeout` has elapsed.
+    pub fn wait_until_ready(&self, timeout: Duration) -> Result<(), Error> {
+        if self.wait_for(timeout, || self.devices.iter().all(Device::is_ready)) {
+            Ok(())
+        } else {
+            Err(Error::Timeout)
+        }
+    }
+
+    /// Map `src` to `dst` and pump every device until the map is active.
+    ///
+    /// Returns [`Error::Timeout`](Error::Timeout) if the map is not ready within `timeout`.
+    pub fn connect(&self, src: &Signal, dst: &Signal, timeout: Duration) -> Result<Map, Error> {
+        let map = Map::try_create(src, dst)?;
+        map.push();
+        if self.wait_for(timeout, || map.is_ready()) {
+            Ok(map)
+        } else {
+            Err(Error::Timeout)
+        }
+    }
+}
+
+/// Set `value` on `output` and check that it arrives at `input` within `timeout`.
+///
+/// The devices owning both signals are polled while waiting, so they must be local.
+/// Both signals must have the type `T`, and only the first element of vector signals is compared.
+///
+/// # Panics
+/// Panics if the value could not be set, or if `input` did not receive `value` from the network in time.
+pub fn assert_propagates<T>(output: &mut Signal, input: &Signal, value: T, timeout: Duration)
+where T: MappableType + Copy + PartialEq + Debug {
+    output.set_value_scalar(&value)
+        .unwrap_or_else(|e| panic!("failed to set {:?} on {}: {:?}", value, signal_path(output), e));
+
+    let mut devices: Vec<mpr_dev> = unsafe { vec![mpr_sig_get_dev(output.handle), mpr_sig_get_dev(input.handle)] };
+    devices.dedup();
+    let deadline = Instant::now() + timeout;
+    let mut received = None;
+    loop {
+        if input.get_status().was_set_remote() {
+            received = input.get_value_scalar::<T>().ok().map(|(v, _)| v);
+            if received == Some(value) {
+                return;
+            }
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            panic!("{:?} set on {} did not reach {} within {:?} (last received: {:?})",
+                value, signal_path(output), signal_path(input), timeout, received);
+        }
+        let block = poll_share(remaining.min(WAIT_POLL_INTERVAL), devices.len()).as_millis() as c_int;
+        for device in &devices {
+            unsafe {
+                mpr_dev_poll(*device, block);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pump_blocks_for_each_device() {
+        let graph = Graph::create();
+        let names: Vec<String> = (0..12).map(|i| format!("rs_pump_{}", i)).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let network = TestNetwork::spawn(&graph, &names, Duration::from_secs(5)).unwrap();
+
+        // 10ms shared between 12 devices would round down to 0ms each
+        let start = Instant::now();
+        network.pump(Duration::from_millis(10));
+        assert!(start.elapsed() >= Duration::from_millis(12));
+    }
+}
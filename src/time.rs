@@ -0,0 +1,212 @@
+//! Timetags used by libmapper to timestamp signal values.
+//!
+//! libmapper uses 64-bit NTP timetags (the same format as OSC) to describe when a value was sampled.
+//! The [Timetag] type wraps libmapper's `mpr_time` and exposes it's arithmetic helpers as rust operators.
+use std::{cmp::Ordering, fmt, ops::{Add, AddAssign, Mul, MulAssign, Sub, SubAssign}, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use crate::{backend::{mpr_time, mpr_time_add, mpr_time_add_dbl, mpr_time_as_dbl, mpr_time_cmp, mpr_time_mul, mpr_time_set_dbl, mpr_time_sub, mpr_type}, device::MappableType};
+
+/// Number of seconds between the NTP epoch (1900-01-01) and the unix epoch (1970-01-01).
+const NTP_UNIX_OFFSET: f64 = 2_208_988_800.0;
+
+/// A 64-bit NTP timetag, as used by libmapper and OSC.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use libmapper_rs::time::Timetag;
+/// let start = Timetag::now();
+/// let later = start + Duration::from_millis(500);
+/// assert!(later > start);
+/// assert!((f64::from(later - start) - 0.5).abs() < 1e-6);
+/// ```
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+pub struct Timetag(pub(crate) mpr_time);
+
+impl Timetag {
+  /// Create a timetag from the raw 64-bit NTP representation used by libmapper.
+  pub fn from_raw(raw: u64) -> Timetag {
+    Timetag(raw)
+  }
+
+  /// Get the raw 64-bit NTP representation used by libmapper.
+  pub fn as_raw(&self) -> u64 {
+    self.0
+  }
+
+  /// Get a timetag representing the current system time.
+  pub fn now() -> Timetag {
+    Timetag::from(SystemTime::now())
+  }
+
+  /// Create a timetag from a number of seconds since the NTP epoch.
+  pub fn from_secs_f64(seconds: f64) -> Timetag {
+    let mut time: mpr_time = 0;
+    unsafe {
+      mpr_time_set_dbl(&mut time, seconds);
+    }
+    Timetag(time)
+  }
+
+  /// Get the number of seconds since the NTP epoch represented by this timetag.
+  pub fn as_secs_f64(&self) -> f64 {
+    unsafe {
+      mpr_time_as_dbl(self.0)
+    }
+  }
+}
+
+impl MappableType for Timetag {
+  fn get_mpr_type() -> mpr_type {
+    mpr_type::MPR_TIME
+  }
+}
+
+impl Add for Timetag {
+  type Output = Timetag;
+  fn add(mut self, rhs: Timetag) -> Timetag {
+    self += rhs;
+    self
+  }
+}
+
+impl AddAssign for Timetag {
+  fn add_assign(&mut self, rhs: Timetag) {
+    unsafe {
+      mpr_time_add(&mut self.0, rhs.0);
+    }
+  }
+}
+
+impl Add<f64> for Timetag {
+  type Output = Timetag;
+  /// Add a number of seconds to this timetag.
+  fn add(mut self, rhs: f64) -> Timetag {
+    self += rhs;
+    self
+  }
+}
+
+impl AddAssign<f64> for Timetag {
+  fn add_assign(&mut self, rhs: f64) {
+    unsafe {
+      mpr_time_add_dbl(&mut self.0, rhs);
+    }
+  }
+}
+
+impl Add<Duration> for Timetag {
+  type Output = Timetag;
+  fn add(self, rhs: Duration) -> Timetag {
+    self + rhs.as_secs_f64()
+  }
+}
+
+impl Sub for Timetag {
+  type Output = Timetag;
+  fn sub(mut self, rhs: Timetag) -> Timetag {
+    self -= rhs;
+    self
+  }
+}
+
+impl SubAssign for Timetag {
+  fn sub_assign(&mut self, rhs: Timetag) {
+    unsafe {
+      mpr_time_sub(&mut self.0, rhs.0);
+    }
+  }
+}
+
+impl Sub<Duration> for Timetag {
+  type Output = Timetag;
+  fn sub(self, rhs: Duration) -> Timetag {
+    self + -rhs.as_secs_f64()
+  }
+}
+
+impl Mul<f64> for Timetag {
+  type Output = Timetag;
+  fn mul(mut self, rhs: f64) -> Timetag {
+    self *= rhs;
+    self
+  }
+}
+
+impl MulAssign<f64> for Timetag {
+  fn mul_assign(&mut self, rhs: f64) {
+    unsafe {
+      mpr_time_mul(&mut self.0, rhs);
+    }
+  }
+}
+
+impl PartialOrd for Timetag {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for Timetag {
+  fn cmp(&self, other: &Self) -> Ordering {
+    unsafe {
+      mpr_time_cmp(self.0, other.0).cmp(&0)
+    }
+  }
+}
+
+impl From<f64> for Timetag {
+  fn from(seconds: f64) -> Self {
+    Timetag::from_secs_f64(seconds)
+  }
+}
+
+impl From<Timetag> for f64 {
+  fn from(time: Timetag) -> Self {
+    time.as_secs_f64()
+  }
+}
+
+/// Interprets the duration as an offset from the NTP epoch (or as an interval).
+impl From<Duration> for Timetag {
+  fn from(duration: Duration) -> Self {
+    Timetag::from_secs_f64(duration.as_secs_f64())
+  }
+}
+
+/// Interprets the timetag as an offset from the NTP epoch (or as an interval), e.g. the difference between two timetags.
+impl From<Timetag> for Duration {
+  fn from(time: Timetag) -> Self {
+    Duration::from_secs_f64(time.as_secs_f64().max(0.0))
+  }
+}
+
+impl From<SystemTime> for Timetag {
+  fn from(time: SystemTime) -> Self {
+    let unix = match time.duration_since(UNIX_EPOCH) {
+      Ok(d) => d.as_secs_f64(),
+      Err(e) => -e.duration().as_secs_f64()
+    };
+    Timetag::from_secs_f64(unix + NTP_UNIX_OFFSET)
+  }
+}
+
+impl From<Timetag> for SystemTime {
+  fn from(time: Timetag) -> Self {
+    let unix = time.as_secs_f64() - NTP_UNIX_OFFSET;
+    if unix >= 0.0 {
+      UNIX_EPOCH + Duration::from_secs_f64(unix)
+    } else {
+      UNIX_EPOCH - Duration::from_secs_f64(-unix)
+    }
+  }
+}
+
+/// Displays the timetag as seconds since the NTP epoch.
+impl fmt::Display for Timetag {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:.6}", self.as_secs_f64())
+  }
+}
@@ -0,0 +1,61 @@
+//! Optional [tracing](https://docs.rs/tracing) integration.
+//!
+//! With the `tracing` feature, libmapper-rs emits events for the lifecycle of libmapper objects:
+//! - `DEBUG`: devices and graphs being created, graph subscriptions, maps being pushed, becoming ready and released,
+//!   and property writes.
+//! - `WARN`: objects that did not become ready before a timeout, and an untested libmapper version being loaded.
+//! - `TRACE`: values set on local signals, which are sampled (see [set_update_sampling]), and every [Map::is_ready](crate::graph::Map::is_ready) check.
+//!
+//! [Device::wait_until_ready](crate::device::Device::wait_until_ready) and [Map::push_and_wait](crate::graph::Map::push_and_wait)
+//! run inside a span, so everything that happens while waiting is grouped under the object being waited on.
+//!
+//! Without the feature, nothing is recorded and the instrumentation compiles to nothing.
+#[cfg(feature = "tracing")]
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Emit a tracing event if the `tracing` feature is enabled, e.g. `event!(debug, name, "created device")`.
+macro_rules! event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        ::tracing::$level!($($arg)+);
+    };
+}
+pub(crate) use event;
+
+/// Enter a span until the returned guard is dropped, e.g. `let _span = span!(DEBUG, "push_and_wait", map = %id);`.
+macro_rules! span {
+    ($level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        let span = ::tracing::span!(::tracing::Level::$level, $($arg)+).entered();
+        #[cfg(not(feature = "tracing"))]
+        let span = $crate::trace::NoSpan;
+        span
+    }};
+}
+pub(crate) use span;
+
+/// Stands in for an entered span when the `tracing` feature is disabled.
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct NoSpan;
+
+#[cfg(feature = "tracing")]
+static UPDATE_SAMPLING: AtomicU32 = AtomicU32::new(100);
+#[cfg(feature = "tracing")]
+static UPDATE_COUNT: AtomicU32 = AtomicU32::new(0);
+
+/// Only record one in every `every` signal updates at `TRACE` level. Defaults to `100`.
+///
+/// Use `1` to record every update, or `0` to record none.
+/// The count is shared by all signals, so a signal updated rarely may not be recorded at all.
+#[cfg(feature = "tracing")]
+pub fn set_update_sampling(every: u32) {
+    UPDATE_SAMPLING.store(every, Ordering::Relaxed);
+}
+
+/// Returns `true` if the current signal update should be recorded.
+#[cfg(feature = "tracing")]
+#[allow(clippy::manual_is_multiple_of)] // `u32::is_multiple_of` needs rust 1.87
+pub(crate) fn sample_update() -> bool {
+    let every = UPDATE_SAMPLING.load(Ordering::Relaxed);
+    every != 0 && UPDATE_COUNT.fetch_add(1, Ordering::Relaxed) % every == 0
+}
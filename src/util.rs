@@ -1,6 +1,6 @@
-use std::ffi::c_void;
+use std::ffi::{c_void, CString};
 
-use crate::bindings::{mpr_list_get_next, mpr_list_get_size};
+use crate::backend::{mpr_list, mpr_list_filter, mpr_list_free, mpr_list_get_next, mpr_op, mpr_prop, mpr_type};
 
 /// Read a list of items from a libmapper list.
 /// 
@@ -18,4 +18,34 @@ pub fn read_list<T, J: Fn(*mut c_void) -> T>(list: *mut *mut c_void, constructor
   }
 
   values
-}
\ No newline at end of file
+}
+
+/// Filter a libmapper list down to the objects whose `MPR_PROP_NAME` is equal to `name`.
+/// 
+/// The passed list is consumed, and the returned list must be read or freed.
+/// Returns `None` if the name contains a NUL byte, as no object can match it.
+pub fn filter_by_name(list: mpr_list, name: &str) -> Option<mpr_list> {
+  if list.is_null() {
+    return Some(list);
+  }
+  let Ok(name_ptr) = CString::new(name) else {
+    unsafe { mpr_list_free(list) };
+    return None;
+  };
+  unsafe {
+    Some(mpr_list_filter(list, mpr_prop::MPR_PROP_NAME, std::ptr::null(), 1, mpr_type::MPR_STR,
+      name_ptr.as_ptr() as *const c_void, mpr_op::MPR_OP_EQ))
+  }
+}
+
+/// Take the first item from a libmapper list, freeing the rest of the list.
+pub fn first_in_list(list: mpr_list) -> Option<*mut c_void> {
+  if list.is_null() {
+    return None;
+  }
+  unsafe {
+    let item = *list;
+    mpr_list_free(list);
+    Some(item)
+  }
+}
@@ -0,0 +1,168 @@
+//! Parsing and checking the version of the loaded libmapper library.
+//!
+//! libmapper-rs is built against the bindings for one libmapper release, but the shared library is only found at runtime.
+//! [check_compatibility] compares the loaded library against [MINIMUM_VERSION] and [TESTED_VERSION],
+//! and is called by [Device::try_create](crate::device::Device::try_create) and [Graph::try_create](crate::graph::Graph::try_create)
+//! so that a mismatched library is reported as an [Error] instead of crashing later.
+//!
+//! # Examples
+//! ```no_run
+//! use libmapper_rs::version::{Compatibility, MapperVersion};
+//! let version = MapperVersion::loaded().unwrap();
+//! println!("libmapper {} is {:?}", version, version.compatibility());
+//! assert!(version.compatibility() != Compatibility::Unsupported);
+//! ```
+use std::{fmt, str::FromStr, sync::OnceLock};
+
+use crate::{backend::mpr_get_version, error::Error, trace};
+
+/// The oldest libmapper release this crate can be used with.
+pub const MINIMUM_VERSION: MapperVersion = MapperVersion::new(2, 4, 9);
+/// The newest libmapper release this crate has been tested against.
+pub const TESTED_VERSION: MapperVersion = MapperVersion::new(2, 4, 9);
+
+/// A libmapper version number.
+///
+/// Versions are ordered by `major`, then `minor`, then `patch`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MapperVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32
+}
+
+/// How well a libmapper version is supported by this crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compatibility {
+    /// The version is between [MINIMUM_VERSION] and [TESTED_VERSION].
+    Supported,
+    /// The version is newer than [TESTED_VERSION] but has the same major version, so it is expected to work.
+    Untested,
+    /// The version is older than [MINIMUM_VERSION] or has a different major version.
+    Unsupported
+}
+
+/// An error that can occur when checking the loaded libmapper version.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VersionError {
+    /// The version reported by libmapper could not be parsed.
+    Invalid(String),
+    /// The loaded libmapper is not compatible with this crate.
+    Unsupported(MapperVersion)
+}
+
+impl fmt::Display for VersionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VersionError::Invalid(version) => write!(f, "invalid libmapper version '{}'", version),
+            VersionError::Unsupported(version) => write!(f, "libmapper {} is not supported, libmapper-rs {} requires libmapper {}.x (>= {})",
+                version, env!("CARGO_PKG_VERSION"), MINIMUM_VERSION.major, MINIMUM_VERSION)
+        }
+    }
+}
+
+impl MapperVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> MapperVersion {
+        MapperVersion {
+            major,
+            minor,
+            patch
+        }
+    }
+
+    /// Get the version of the loaded libmapper library.
+    pub fn loaded() -> Result<MapperVersion, Error> {
+        let version = crate::try_get_mapper_version()?;
+        Ok(version.parse()?)
+    }
+
+    /// Check how well this version is supported by this crate.
+    pub fn compatibility(&self) -> Compatibility {
+        if self.major != MINIMUM_VERSION.major || *self < MINIMUM_VERSION {
+            Compatibility::Unsupported
+        } else if *self > TESTED_VERSION {
+            Compatibility::Untested
+        } else {
+            Compatibility::Supported
+        }
+    }
+}
+
+impl fmt::Display for MapperVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for MapperVersion {
+    type Err = VersionError;
+
+    /// Parse a version like `2.4.9`.
+    ///
+    /// A missing patch number is read as `0`, and anything after the numbers (e.g. `2.4.9+12-g1a2b3c` from a git build) is ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || VersionError::Invalid(s.to_string());
+        let mut parts = s.trim().split('.').map(|part| {
+            let digits = part.find(|c: char| !c.is_ascii_digit()).unwrap_or(part.len());
+            part[..digits].parse::<u32>().ok()
+        });
+        let major = parts.next().flatten().ok_or_else(invalid)?;
+        let minor = parts.next().flatten().ok_or_else(invalid)?;
+        let patch = parts.next().flatten().unwrap_or(0);
+        Ok(MapperVersion::new(major, minor, patch))
+    }
+}
+
+/// Check that the loaded libmapper is compatible with this crate.
+///
+/// The check only runs once, later calls return the same result.
+/// Returns the loaded version if it is [Supported](Compatibility::Supported) or [Untested](Compatibility::Untested),
+/// otherwise returns [`VersionError::Unsupported`](VersionError::Unsupported).
+pub fn check_compatibility() -> Result<MapperVersion, Error> {
+    static CHECKED: OnceLock<Result<MapperVersion, VersionError>> = OnceLock::new();
+    let result = CHECKED.get_or_init(|| {
+        let raw = unsafe { std::ffi::CStr::from_ptr(mpr_get_version()) }.to_string_lossy();
+        let version: MapperVersion = raw.parse()?;
+        match version.compatibility() {
+            Compatibility::Unsupported => Err(VersionError::Unsupported(version)),
+            Compatibility::Untested => {
+                trace::event!(warn, %version, tested = %TESTED_VERSION, "libmapper is newer than the version libmapper-rs was tested against");
+                Ok(version)
+            },
+            Compatibility::Supported => Ok(version)
+        }
+    });
+    Ok(result.clone()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_versions() {
+        assert_eq!("2.4.9".parse(), Ok(MapperVersion::new(2, 4, 9)));
+        assert_eq!("2.4.9+12-g1a2b3c".parse(), Ok(MapperVersion::new(2, 4, 9)));
+        assert_eq!(" 2.5.0\n".parse(), Ok(MapperVersion::new(2, 5, 0)));
+        assert_eq!("3.0".parse(), Ok(MapperVersion::new(3, 0, 0)));
+        assert_eq!("".parse::<MapperVersion>(), Err(VersionError::Invalid(String::new())));
+        assert_eq!("2".parse::<MapperVersion>(), Err(VersionError::Invalid("2".to_string())));
+        assert_eq!("v2.4.9".parse::<MapperVersion>(), Err(VersionError::Invalid("v2.4.9".to_string())));
+    }
+
+    #[test]
+    fn checks_compatibility() {
+        assert_eq!(MapperVersion::new(2, 4, 9).compatibility(), Compatibility::Supported);
+        assert_eq!(MapperVersion::new(2, 4, 10).compatibility(), Compatibility::Untested);
+        assert_eq!(MapperVersion::new(2, 5, 0).compatibility(), Compatibility::Untested);
+        assert_eq!(MapperVersion::new(2, 4, 8).compatibility(), Compatibility::Unsupported);
+        assert_eq!(MapperVersion::new(3, 0, 0).compatibility(), Compatibility::Unsupported);
+        assert_eq!(MapperVersion::new(1, 9, 9).compatibility(), Compatibility::Unsupported);
+    }
+
+    #[test]
+    fn unsupported_message_names_supported_range() {
+        let message = VersionError::Unsupported(MapperVersion::new(3, 0, 0)).to_string();
+        assert!(message.ends_with("requires libmapper 2.x (>= 2.4.9)"), "{}", message);
+    }
+}
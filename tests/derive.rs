@@ -0,0 +1,35 @@
+//! Tests for `#[derive(MapperSignals)]`, run with `cargo test --test derive --features derive`.
+use libmapper_rs::{constants::mpr_prop, device::Device, object::MapperObject, signal::MapperSignals};
+
+/// Field names that collide with the names used inside the generated code.
+#[derive(MapperSignals, Default)]
+struct Shadowing {
+    #[signal(dir = "out", min = 1.0, max = 2.0)]
+    device: f32,
+    #[signal(dir = "in", min = -1.0, max = 1.0)]
+    min: f64,
+    #[signal(dir = "in", min = [0, 0], max = [10, 20])]
+    max: [i32; 2],
+    #[signal(dir = "out", unit = "Hz")]
+    signals: f32
+}
+
+#[test]
+fn field_names_do_not_shadow_generated_code() {
+    let device = Device::create("rs_derive_test");
+    let mut signals = Shadowing::create_signals(&device).unwrap();
+
+    assert_eq!(signals.device.get_property_vec::<f32>(mpr_prop::MPR_PROP_MIN), Ok(vec![1.0]));
+    assert_eq!(signals.device.get_property_vec::<f32>(mpr_prop::MPR_PROP_MAX), Ok(vec![2.0]));
+    assert_eq!(signals.min.get_property_vec::<f64>(mpr_prop::MPR_PROP_MIN), Ok(vec![-1.0]));
+    assert_eq!(signals.min.get_property_vec::<f64>(mpr_prop::MPR_PROP_MAX), Ok(vec![1.0]));
+    assert_eq!(signals.max.get_property_vec::<i32>(mpr_prop::MPR_PROP_MIN), Ok(vec![0, 0]));
+    assert_eq!(signals.max.get_property_vec::<i32>(mpr_prop::MPR_PROP_MAX), Ok(vec![10, 20]));
+    assert_eq!(signals.signals.get_property_str(mpr_prop::MPR_PROP_UNIT).as_deref(), Ok("Hz"));
+    assert_eq!(signals.max.get_vector_length(), 2);
+
+    let mut values = Shadowing { device: 1.5, ..Default::default() };
+    values.write_all(&mut signals).unwrap();
+    assert_eq!(signals.device.get_value_scalar::<f32>().unwrap().0, 1.5);
+    values.read_all(&signals);
+}